@@ -1,6 +1,7 @@
 use std::future::Future;
 
-use anyhow::{anyhow, Result};
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
 use wasm_bindgen::{
     closure::{WasmClosure, WasmClosureFnOnce},
     prelude::Closure,
@@ -8,52 +9,75 @@ use wasm_bindgen::{
 };
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{
-    js_sys::ArrayBuffer, CanvasRenderingContext2d, Document, Element, HtmlCanvasElement,
-    HtmlElement, HtmlImageElement, Response, Window,
+    js_sys::{Array, ArrayBuffer, JSON},
+    Blob, BlobPropertyBag, CanvasRenderingContext2d, Document, Element, Gamepad, GamepadButton,
+    HtmlAnchorElement, HtmlCanvasElement, HtmlElement, HtmlImageElement, Response, Storage, Url,
+    Window,
 };
 
+use crate::engine::error::EngineError;
+
+/// Logs at `level` for the calling module, formatting only when the runtime
+/// filter lets the record through.
+macro_rules! log_at {
+    ( $level:expr, $ ( $t:tt )* ) => {
+        if $crate::engine::logging::enabled($level, module_path!()) {
+            $crate::engine::logging::log($level, module_path!(), format_args!( $( $t )* ));
+        }
+    };
+}
+
+macro_rules! error {
+    ( $ ( $t:tt )* ) => {
+        log_at!($crate::engine::logging::Level::Error, $( $t )*)
+    };
+}
+
+macro_rules! warn {
+    ( $ ( $t:tt )* ) => {
+        log_at!($crate::engine::logging::Level::Warn, $( $t )*)
+    };
+}
+
 macro_rules! log {
     ( $ ( $t:tt )* ) => {
-        web_sys::console::log_1(&format!( $( $t )* ).into());
+        log_at!($crate::engine::logging::Level::Info, $( $t )*)
     };
 }
 
-macro_rules! error {
-    ( $ ( $t:tt)* ) => {
-        web_sys::console::error_1(&format!( $( $t )* ).into())
+macro_rules! debug {
+    ( $ ( $t:tt )* ) => {
+        log_at!($crate::engine::logging::Level::Debug, $( $t )*)
     };
 }
 
+pub mod ui;
+
 pub fn window() -> Result<Window> {
-    web_sys::window().ok_or_else(|| anyhow!("No window found"))
+    Ok(web_sys::window().ok_or(EngineError::Missing("window"))?)
 }
 
 pub fn document() -> Result<Document> {
-    window()?
+    Ok(window()?
         .document()
-        .ok_or_else(|| anyhow!("No document found"))
+        .ok_or(EngineError::Missing("document"))?)
 }
 
 pub fn canvas() -> Result<HtmlCanvasElement> {
-    document()?
+    Ok(document()?
         .get_element_by_id("canvas")
-        .ok_or_else(|| anyhow!("No canvas element with ID 'canvas'"))?
+        .ok_or_else(|| EngineError::ElementNotFound("canvas".into()))?
         .dyn_into::<web_sys::HtmlCanvasElement>()
-        .map_err(|element| anyhow!("Error converting {:#?} to HtmlCanvasElement", element))
+        .map_err(|element| EngineError::conversion(element, "HtmlCanvasElement"))?)
 }
 
 pub fn context() -> Result<CanvasRenderingContext2d> {
-    canvas()?
+    Ok(canvas()?
         .get_context("2d")
-        .map_err(|js_value| anyhow!("Error getting 2d context {:#?}", js_value))?
-        .ok_or_else(|| anyhow!("No 2d context found"))?
+        .map_err(|js_value| EngineError::browser("get 2d context", js_value))?
+        .ok_or(EngineError::Missing("2d context"))?
         .dyn_into::<CanvasRenderingContext2d>()
-        .map_err(|element| {
-            anyhow!(
-                "Error converting {:#?} to CanvasRenderingContext2d",
-                element
-            )
-        })
+        .map_err(|element| EngineError::conversion(element, "CanvasRenderingContext2d"))?)
 }
 
 pub fn spawn_local<F>(future: F)
@@ -64,45 +88,46 @@ where
 }
 
 pub async fn fetch_with_str(resource: &str) -> Result<JsValue> {
-    JsFuture::from(window()?.fetch_with_str(resource))
+    Ok(JsFuture::from(window()?.fetch_with_str(resource))
         .await
-        .map_err(|err| anyhow!("Error fetching {:#?}", err))
+        .map_err(|err| EngineError::browser(format!("fetch {}", resource), err))?)
 }
 
 pub async fn fetch_response(resource: &str) -> Result<Response> {
-    fetch_with_str(resource)
+    Ok(fetch_with_str(resource)
         .await?
         .dyn_into()
-        .map_err(|err| anyhow!("Error converting fetch to response {:#?}", err))
+        .map_err(|err| EngineError::conversion(err, "Response"))?)
 }
 
 pub async fn fetch_json(json_path: &str) -> Result<JsValue> {
     let response = fetch_response(json_path).await?;
 
-    JsFuture::from(
+    Ok(JsFuture::from(
         response
             .json()
-            .map_err(|err| anyhow!("Could not get JSON from response {:#?}", err))?,
+            .map_err(|err| EngineError::browser("get JSON from response", err))?,
     )
     .await
-    .map_err(|err| anyhow!("Error fetching JSON {:#?}", err))
+    .map_err(|err| EngineError::browser(format!("fetch JSON {}", json_path), err))?)
 }
 
 pub async fn fetch_array_buffer(resource: &str) -> Result<ArrayBuffer> {
     let array_buffer = fetch_response(resource)
         .await?
         .array_buffer()
-        .map_err(|err| anyhow!("Error loading array buffer {:#?}", err))?;
+        .map_err(|err| EngineError::browser("load array buffer", err))?;
 
-    JsFuture::from(array_buffer)
+    Ok(JsFuture::from(array_buffer)
         .await
-        .map_err(|err| anyhow!("Error converting array buffer into a future {:#?}", err))?
+        .map_err(|err| EngineError::browser("convert array buffer into a future", err))?
         .dyn_into()
-        .map_err(|err| anyhow!("Error converting raw JsValue into ArrayBuffer {:#?}", err))
+        .map_err(|err| EngineError::conversion(err, "ArrayBuffer"))?)
 }
 
 pub fn new_image() -> Result<HtmlImageElement> {
-    HtmlImageElement::new().map_err(|err| anyhow!("Could not create HtmlImageElement {:#?}", err))
+    Ok(HtmlImageElement::new()
+        .map_err(|err| EngineError::browser("create HtmlImageElement", err))?)
 }
 
 pub fn closure_once<F, A, R>(fn_once: F) -> Closure<F::FnMut>
@@ -114,9 +139,9 @@ where
 
 pub type LoopClosure = Closure<dyn FnMut(f64)>;
 pub fn request_animation_frame(callback: &LoopClosure) -> Result<i32> {
-    window()?
+    Ok(window()?
         .request_animation_frame(callback.as_ref().unchecked_ref())
-        .map_err(|err| anyhow!("Cannot request animation frame {:#?}", err))
+        .map_err(|err| EngineError::browser("request animation frame", err))?)
 }
 
 pub fn create_raf_closure<F>(f: F) -> LoopClosure
@@ -133,54 +158,207 @@ where
     Closure::wrap(data)
 }
 
+pub fn add_window_listener(event: &str, listener: &Closure<dyn FnMut()>) -> Result<()> {
+    Ok(window()?
+        .add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())
+        .map_err(|err| EngineError::browser(format!("listen to {}", event), err))?)
+}
+
+pub fn remove_window_listener(event: &str, listener: &Closure<dyn FnMut()>) -> Result<()> {
+    Ok(window()?
+        .remove_event_listener_with_callback(event, listener.as_ref().unchecked_ref())
+        .map_err(|err| EngineError::browser(format!("stop listening to {}", event), err))?)
+}
+
+/// Which buttons of the first connected gamepad are down, by standard mapping index.
+pub fn gamepad_buttons() -> Result<Vec<bool>> {
+    let gamepads = window()?
+        .navigator()
+        .get_gamepads()
+        .map_err(|err| EngineError::browser("read gamepads", err))?;
+    Ok(gamepads
+        .iter()
+        .find_map(|gamepad| gamepad.dyn_into::<Gamepad>().ok())
+        .map(|gamepad| {
+            gamepad
+                .buttons()
+                .iter()
+                .map(|button| {
+                    button
+                        .dyn_into::<GamepadButton>()
+                        .is_ok_and(|button| button.pressed())
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// `resource` with a query that makes the browser fetch it again.
+pub fn cache_busted(resource: &str) -> Result<String> {
+    let separator = if resource.contains('?') { '&' } else { '?' };
+    Ok(format!("{}{}v={}", resource, separator, now()?))
+}
+
 pub fn now() -> Result<f64> {
     Ok(window()?
         .performance()
-        .ok_or_else(|| anyhow!("Performance object not found"))?
+        .ok_or(EngineError::Missing("performance object"))?
         .now())
 }
 
-pub fn find_html_element_by_id(id: &str) -> Result<HtmlElement> {
-    document()
-        .and_then(|doc| {
-            doc.get_element_by_id(id)
-                .ok_or_else(|| anyhow!("Element with id {} not found", id))
-        })
-        .and_then(|element| {
-            element
-                .dyn_into()
-                .map_err(|err| anyhow!("Could not convert to HtmlElement {:#?}", err))
-        })
+fn local_storage() -> Result<Storage> {
+    Ok(window()?
+        .local_storage()
+        .map_err(|err| EngineError::browser("access local storage", err))?
+        .ok_or(EngineError::Missing("local storage"))?)
+}
+
+pub fn load_json<T: DeserializeOwned>(key: &str) -> Result<Option<T>> {
+    let item = local_storage()?
+        .get_item(key)
+        .map_err(|err| EngineError::browser(format!("read {} from local storage", key), err))?;
+    match item {
+        Some(json) => {
+            let value = JSON::parse(&json)
+                .map_err(|err| EngineError::browser(format!("parse {}", key), err))?;
+            Ok(Some(
+                serde_wasm_bindgen::from_value(value)
+                    .map_err(|err| EngineError::resource(key, err))?,
+            ))
+        }
+        None => Ok(None),
+    }
+}
+
+pub fn store_json<T: Serialize>(key: &str, value: &T) -> Result<()> {
+    let json = to_json(value).with_context(|| format!("Could not serialize {}", key))?;
+    Ok(local_storage()?
+        .set_item(key, &json)
+        .map_err(|err| EngineError::browser(format!("write {} to local storage", key), err))?)
 }
 
-fn find_ui() -> Result<Element> {
-    document().and_then(|doc| {
-        doc.get_element_by_id("ui")
-            .ok_or_else(|| anyhow!("UI element not found"))
-    })
+pub fn remove_item(key: &str) -> Result<()> {
+    Ok(local_storage()?
+        .remove_item(key)
+        .map_err(|err| EngineError::browser(format!("remove {} from local storage", key), err))?)
 }
 
-pub fn draw_ui(html: &str) -> Result<()> {
-    find_ui()?
-        .insert_adjacent_html("afterbegin", html)
-        .map_err(|err| anyhow!("Could not insert html {:#?}", err))
+pub fn to_json<T: Serialize>(value: &T) -> Result<String> {
+    let value = serde_wasm_bindgen::to_value(value)
+        .map_err(|err| EngineError::resource("JSON value", err))?;
+    Ok(JSON::stringify(&value)
+        .map_err(|err| EngineError::browser("serialize JSON", err))?
+        .into())
 }
 
-pub fn hide_ui() -> Result<()> {
-    let ui = find_ui()?;
+/// Saves `contents` as a file through the browser's download prompt.
+pub fn download(file_name: &str, contents: &str) -> Result<()> {
+    let options = BlobPropertyBag::new();
+    options.set_type("application/json");
+    let parts = Array::of1(&JsValue::from_str(contents));
+    let blob = Blob::new_with_str_sequence_and_options(&parts, &options)
+        .map_err(|err| EngineError::browser("create blob", err))?;
+    let url = Url::create_object_url_with_blob(&blob)
+        .map_err(|err| EngineError::browser("create object URL", err))?;
+    let link = create_element("a")?
+        .dyn_into::<HtmlAnchorElement>()
+        .map_err(|element| EngineError::conversion(element, "HtmlAnchorElement"))?;
+    link.set_href(&url);
+    link.set_download(file_name);
+    link.click();
+    Url::revoke_object_url(&url).map_err(|err| EngineError::browser("revoke object URL", err))?;
+    Ok(())
+}
+
+/// The value of `name` in the page's query string.
+pub fn query_param(name: &str) -> Result<Option<String>> {
+    let href = window()?
+        .location()
+        .href()
+        .map_err(|err| EngineError::browser("read the page URL", err))?;
+    let url = Url::new(&href).map_err(|err| EngineError::browser("parse the page URL", err))?;
+    Ok(url.search_params().get(name))
+}
+
+pub fn focus_canvas() -> Result<()> {
+    Ok(canvas()?
+        .focus()
+        .map_err(|err| EngineError::browser("set focus to canvas", err))?)
+}
+
+const ERROR_OVERLAY_ID: &str = "error_overlay";
+
+/// Lists the error chain, then the recent log for attaching to a bug report.
+pub fn draw_error_overlay(messages: &[String], log: &[String]) -> Result<()> {
+    let document = document()?;
+    if document.get_element_by_id(ERROR_OVERLAY_ID).is_some() {
+        return Ok(());
+    }
+
+    let overlay = create_element("div")?;
+    overlay.set_id(ERROR_OVERLAY_ID);
+
+    let title = create_element("h1")?;
+    title.set_text_content(Some("Something went wrong"));
+    append_child(&overlay, &title)?;
+
+    let chain = create_element("ol")?;
+    for message in messages {
+        let item = create_element("li")?;
+        item.set_text_content(Some(message));
+        append_child(&chain, &item)?;
+    }
+    append_child(&overlay, &chain)?;
 
-    if let Some(child) = ui.first_child() {
-        ui.remove_child(&child)
-            .map(|_removed_child| ())
-            .map_err(|err| anyhow!("Failed to remove child {:#?}", err))
-            .and_then(|_unit| {
-                canvas()?
-                    .focus()
-                    .map_err(|err| anyhow!("Could not set focus to canvas {:#?}", err))
-            })
-    } else {
-        Ok(())
+    if !log.is_empty() {
+        let details = create_element("details")?;
+        let summary = create_element("summary")?;
+        summary.set_text_content(Some("Recent log"));
+        append_child(&details, &summary)?;
+        let lines = create_element("pre")?;
+        lines.set_text_content(Some(&log.join("\n")));
+        append_child(&details, &lines)?;
+        append_child(&overlay, &details)?;
     }
+
+    let restart = create_element("button")?;
+    restart.set_text_content(Some("Restart"));
+    let on_click = closure_wrap(Box::new(|| {
+        if let Err(err) = reload() {
+            error!("Could not restart the game {:#?}", err);
+        }
+    }) as Box<dyn FnMut()>);
+    restart
+        .dyn_ref::<HtmlElement>()
+        .ok_or_else(|| EngineError::conversion(&restart, "HtmlElement"))?
+        .set_onclick(Some(on_click.as_ref().unchecked_ref()));
+    on_click.forget();
+    append_child(&overlay, &restart)?;
+
+    let body = document
+        .body()
+        .ok_or(EngineError::Missing("document body"))?;
+    append_child(&body, &overlay)
+}
+
+fn create_element(tag: &str) -> Result<Element> {
+    Ok(document()?
+        .create_element(tag)
+        .map_err(|err| EngineError::browser(format!("create {} element", tag), err))?)
+}
+
+fn append_child(parent: &Element, child: &Element) -> Result<()> {
+    parent
+        .append_child(child)
+        .map_err(|err| EngineError::browser("append child", err))?;
+    Ok(())
+}
+
+pub fn reload() -> Result<()> {
+    Ok(window()?
+        .location()
+        .reload()
+        .map_err(|err| EngineError::browser("reload the page", err))?)
 }
 
 #[cfg(test)]
@@ -196,4 +374,4 @@ mod tests {
         let json = fetch_json("not_here.json").await;
         assert!(json.is_err());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,184 @@
+use anyhow::Result;
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use wasm_bindgen::{prelude::Closure, JsCast};
+use web_sys::{Element, Event, EventTarget, HtmlInputElement, KeyboardEvent};
+
+use super::{append_child, closure_wrap, create_element, focus_canvas, EngineError};
+
+const UI_ID: &str = "ui";
+const CANCEL_KEYS: [&str; 2] = ["Escape", "Backquote"];
+
+/// A DOM panel inside `#ui`. Panels own their elements and listeners: dropping
+/// one removes its element from the page and unregisters everything it listened
+/// to, so any number of panels can come and go independently.
+pub struct Panel {
+    root: Element,
+    listeners: Vec<Listener>,
+}
+
+impl Panel {
+    pub fn new(id: &str) -> Result<Self> {
+        let root = create_element("div")?;
+        root.set_id(id);
+        root.set_class_name("panel");
+        append_child(&ui_root()?, &root)?;
+        Ok(Panel {
+            root,
+            listeners: vec![],
+        })
+    }
+
+    pub fn heading(&mut self, text: &str) -> Result<()> {
+        self.add_text("h1", text)
+    }
+
+    pub fn text(&mut self, text: &str) -> Result<()> {
+        self.add_text("p", text)
+    }
+
+    /// An ordered list, for things like high score tables.
+    pub fn list(&mut self, items: &[String]) -> Result<()> {
+        let list = create_element("ol")?;
+        for item in items {
+            let element = create_element("li")?;
+            element.set_text_content(Some(item));
+            append_child(&list, &element)?;
+        }
+        append_child(&self.root, &list)
+    }
+
+    /// A button that sends `()` each time it is clicked.
+    pub fn button(&mut self, label: &str) -> Result<UnboundedReceiver<()>> {
+        let button = create_element("button")?;
+        button.set_text_content(Some(label));
+        append_child(&self.root, &button)?;
+        let (sender, receiver) = unbounded();
+        self.listen(&button, "click", sender, true, |_| Some(()))?;
+        Ok(receiver)
+    }
+
+    /// A focused single line text box. Enter submits and clears it; Escape or
+    /// backtick cancels and hands the keyboard back to the game.
+    pub fn text_input(&mut self, placeholder: &str) -> Result<UnboundedReceiver<TextInput>> {
+        let input = create_element("input")?
+            .dyn_into::<HtmlInputElement>()
+            .map_err(|element| EngineError::conversion(element, "HtmlInputElement"))?;
+        input.set_type("text");
+        input.set_placeholder(placeholder);
+        append_child(&self.root, &input)?;
+        input
+            .focus()
+            .map_err(|err| EngineError::browser("focus text input", err))?;
+        let (sender, receiver) = unbounded();
+        let target = input.clone();
+        self.listen(&input, "keydown", sender, false, move |event| {
+            let code = event.dyn_ref::<KeyboardEvent>()?.code();
+            if code == "Enter" {
+                let line = target.value();
+                target.set_value("");
+                Some(TextInput::Submit(line))
+            } else if CANCEL_KEYS.contains(&code.as_str()) {
+                event.prevent_default();
+                if let Err(err) = focus_canvas() {
+                    error!("Could not give focus back to the canvas {:#?}", err);
+                }
+                Some(TextInput::Cancel)
+            } else {
+                None
+            }
+        })?;
+        Ok(receiver)
+    }
+
+    /// Preformatted text that can be replaced after the panel is built.
+    pub fn output(&mut self) -> Result<Output> {
+        let element = create_element("pre")?;
+        append_child(&self.root, &element)?;
+        Ok(Output { element })
+    }
+
+    fn add_text(&mut self, tag: &str, text: &str) -> Result<()> {
+        let element = create_element(tag)?;
+        element.set_text_content(Some(text));
+        append_child(&self.root, &element)
+    }
+
+    /// Sends whatever `read` makes of each `event` on `target`, then hands the
+    /// keyboard back to the game unless the element takes typing.
+    fn listen<T: 'static>(
+        &mut self,
+        target: &Element,
+        event: &'static str,
+        mut sender: UnboundedSender<T>,
+        hand_back_focus: bool,
+        read: impl Fn(&Event) -> Option<T> + 'static,
+    ) -> Result<()> {
+        let closure = closure_wrap(Box::new(move |event: Event| {
+            if let Some(value) = read(&event) {
+                if let Err(err) = sender.start_send(value) {
+                    error!("Could not send ui event {:#?}", err);
+                }
+            }
+            if hand_back_focus {
+                if let Err(err) = focus_canvas() {
+                    error!("Could not give focus back to the canvas {:#?}", err);
+                }
+            }
+        }) as Box<dyn FnMut(Event)>);
+        target
+            .add_event_listener_with_callback(event, closure.as_ref().unchecked_ref())
+            .map_err(|err| EngineError::browser(format!("listen to {}", event), err))?;
+        self.listeners.push(Listener {
+            target: target.clone().into(),
+            event,
+            closure,
+        });
+        Ok(())
+    }
+}
+
+impl Drop for Panel {
+    fn drop(&mut self) {
+        self.listeners.clear();
+        self.root.remove();
+    }
+}
+
+pub enum TextInput {
+    Submit(String),
+    Cancel,
+}
+
+pub struct Output {
+    element: Element,
+}
+
+impl Output {
+    pub fn set_lines(&self, lines: &[String]) {
+        self.element.set_text_content(Some(&lines.join("\n")));
+        self.element.set_scroll_top(self.element.scroll_height());
+    }
+}
+
+struct Listener {
+    target: EventTarget,
+    event: &'static str,
+    closure: Closure<dyn FnMut(Event)>,
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        if let Err(err) = self
+            .target
+            .remove_event_listener_with_callback(self.event, self.closure.as_ref().unchecked_ref())
+        {
+            error!("Could not stop listening to {} {:#?}", self.event, err);
+        }
+    }
+}
+
+fn ui_root() -> Result<Element> {
+    Ok(super::document()?
+        .get_element_by_id(UI_ID)
+        .ok_or_else(|| EngineError::ElementNotFound(UI_ID.into()))?)
+}
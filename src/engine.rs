@@ -1,11 +1,26 @@
 pub mod audio;
+pub mod broad_phase;
+pub mod camera;
+pub mod console;
+pub mod debug;
+pub mod error;
+pub mod events;
 pub mod game_loop;
+pub mod hot_reload;
 pub mod image;
+pub mod juice;
 pub mod key_state;
+pub mod logging;
+pub mod parallax;
+pub mod particles;
 pub mod point;
+pub mod profiler;
 pub mod rect;
 pub mod renderer;
 pub mod sprite_sheet;
+pub mod tween;
+pub mod ui;
+pub mod vector;
 
 pub use game_loop::{Game, GameLoop};
 pub use image::Image;
@@ -14,27 +29,48 @@ pub use point::Point;
 pub use rect::Rect;
 pub use renderer::Renderer;
 pub use sprite_sheet::{Cell, SpriteSheet};
+pub use vector::Vector;
 
 use std::{cell::RefCell, rc::Rc, sync::Mutex};
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use futures::channel::{
-    mpsc::{unbounded, UnboundedReceiver},
+    mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
     oneshot::channel,
 };
 use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
-use web_sys::{HtmlElement, HtmlImageElement};
+use web_sys::{HtmlCanvasElement, HtmlImageElement, MouseEvent};
 
 use crate::browser;
 
+use self::error::EngineError;
+
 const FRAME_SIZE: f32 = 1.0 / 60.0 * 1000.0;
 
 pub async fn load_image(source: &str) -> Result<HtmlImageElement> {
     let image = browser::new_image()?;
+    load_into(&image, source).await?;
+    Ok(image)
+}
 
+/// Loads `source` again for an image that is already in use, bypassing the
+/// browser cache, so everything drawing it picks up the change. A fresh copy is
+/// loaded first and the image in use is left alone if that fails.
+pub async fn reload_image(image: &HtmlImageElement, source: &str) -> Result<()> {
+    let fresh = load_image(&browser::cache_busted(source)?).await?;
+    // The loaded copy is in the document's list of available images, so the
+    // image in use switches to it straight away without loading anything
+    image.set_onload(None);
+    image.set_onerror(None);
+    image.set_src(&fresh.src());
+    Ok(())
+}
+
+async fn load_into(image: &HtmlImageElement, source: &str) -> Result<()> {
     let (complete_tx, complete_rx) = channel::<Result<()>>();
     let success_tx = Rc::new(Mutex::new(Some(complete_tx)));
     let error_tx = Rc::clone(&success_tx);
+    let resource = source.to_string();
     let success_callback = browser::closure_once(move || {
         if let Some(success_tx) = success_tx.lock().ok().and_then(|mut opt| opt.take()) {
             if let Err(err) = success_tx.send(Ok(())) {
@@ -47,7 +83,8 @@ pub async fn load_image(source: &str) -> Result<HtmlImageElement> {
     });
     let error_callback: Closure<dyn FnMut(JsValue)> = browser::closure_once(move |err| {
         if let Some(error_tx) = error_tx.lock().ok().and_then(|mut opt| opt.take()) {
-            if let Err(err) = error_tx.send(Err(anyhow!("Error loading image: {:#?}", err))) {
+            let load_error = EngineError::resource(&resource, format!("{:?}", err));
+            if let Err(err) = error_tx.send(Err(load_error.into())) {
                 error!(
                     "Unable to send error message after trying to load image {:#?}",
                     err
@@ -62,20 +99,30 @@ pub async fn load_image(source: &str) -> Result<HtmlImageElement> {
 
     complete_rx.await??;
 
-    Ok(image)
+    Ok(())
 }
 
 enum KeyPress {
     KeyUp(web_sys::KeyboardEvent),
     KeyDown(web_sys::KeyboardEvent),
+    PointerMove(Point),
+    PointerDown(Point),
+    PointerUp(Point),
+    Blur,
 }
 
 fn prepare_input() -> Result<UnboundedReceiver<KeyPress>> {
     let (keyevent_sender, keyevent_receiver) = unbounded();
     let keyup_sender = Rc::new(RefCell::new(keyevent_sender));
     let keydown_sender = Rc::clone(&keyup_sender);
+    let pointer_sender = Rc::clone(&keyup_sender);
+    let blur_sender = Rc::clone(&keyup_sender);
 
     let onkeydown = browser::closure_wrap(Box::new(move |keycode: web_sys::KeyboardEvent| {
+        // F3 is find-next and F6 focuses the address bar in some browsers
+        if [debug::DEBUG_KEY, hot_reload::RELOAD_KEY].contains(&keycode.code().as_str()) {
+            keycode.prevent_default();
+        }
         if let Err(err) = keydown_sender
             .borrow_mut()
             .start_send(KeyPress::KeyDown(keycode))
@@ -93,62 +140,77 @@ fn prepare_input() -> Result<UnboundedReceiver<KeyPress>> {
         }
     }) as Box<dyn FnMut(web_sys::KeyboardEvent)>);
 
-    browser::canvas()
-        .unwrap()
-        .set_onkeydown(Some(onkeydown.as_ref().unchecked_ref()));
-    browser::canvas()
-        .unwrap()
-        .set_onkeyup(Some(onkeyup.as_ref().unchecked_ref()));
+    // Keys released while something else has focus never send a key up here
+    let onblur = browser::closure_wrap(Box::new(move || {
+        if let Err(err) = blur_sender.borrow_mut().start_send(KeyPress::Blur) {
+            error!("Could not send blur event {:#?}", err);
+        }
+    }) as Box<dyn FnMut()>);
+
+    let canvas = browser::canvas()?;
+    canvas.set_onkeydown(Some(onkeydown.as_ref().unchecked_ref()));
+    canvas.set_onkeyup(Some(onkeyup.as_ref().unchecked_ref()));
+    canvas.set_onblur(Some(onblur.as_ref().unchecked_ref()));
     onkeydown.forget();
     onkeyup.forget();
+    onblur.forget();
+
+    let onpointermove = pointer_closure(&canvas, &pointer_sender, KeyPress::PointerMove);
+    let onpointerdown = pointer_closure(&canvas, &pointer_sender, KeyPress::PointerDown);
+    let onpointerup = pointer_closure(&canvas, &pointer_sender, KeyPress::PointerUp);
+    canvas.set_onpointermove(Some(onpointermove.as_ref().unchecked_ref()));
+    canvas.set_onpointerdown(Some(onpointerdown.as_ref().unchecked_ref()));
+    canvas.set_onpointerup(Some(onpointerup.as_ref().unchecked_ref()));
+    onpointermove.forget();
+    onpointerdown.forget();
+    onpointerup.forget();
 
     Ok(keyevent_receiver)
 }
 
+fn pointer_closure(
+    canvas: &HtmlCanvasElement,
+    sender: &Rc<RefCell<UnboundedSender<KeyPress>>>,
+    press: fn(Point) -> KeyPress,
+) -> Closure<dyn FnMut(MouseEvent)> {
+    let canvas = canvas.clone();
+    let sender = Rc::clone(sender);
+    browser::closure_wrap(Box::new(move |event: MouseEvent| {
+        let position = canvas_position(&canvas, &event);
+        if let Err(err) = sender.borrow_mut().start_send(press(position)) {
+            error!("Could not send pointer event {:#?}", err);
+        }
+    }) as Box<dyn FnMut(MouseEvent)>)
+}
+
+/// Pointer events are in CSS pixels, which differ from canvas pixels when the canvas is scaled.
+fn canvas_position(canvas: &HtmlCanvasElement, event: &MouseEvent) -> Point {
+    let scale = |size: u32, client_size: i32| size as f32 / client_size.max(1) as f32;
+    Point {
+        x: (event.offset_x() as f32 * scale(canvas.width(), canvas.client_width())).round() as i32,
+        y: (event.offset_y() as f32 * scale(canvas.height(), canvas.client_height())).round()
+            as i32,
+    }
+}
+
 fn process_input(state: &mut KeyState, keyevent_receiver: &mut UnboundedReceiver<KeyPress>) {
     loop {
         match keyevent_receiver.try_next() {
             Ok(None) => break,
             Err(_err) => break,
             Ok(Some(evt)) => match evt {
-                KeyPress::KeyDown(evt) => state.set_pressed(&evt.code(), evt),
+                KeyPress::KeyDown(evt) => {
+                    debug!("Key pressed: {}", evt.code());
+                    state.set_pressed(&evt.code());
+                }
                 KeyPress::KeyUp(evt) => state.set_released(&evt.code()),
+                KeyPress::PointerMove(position) => state.set_pointer_position(position),
+                KeyPress::PointerDown(position) => state.set_pointer_down(position),
+                KeyPress::PointerUp(position) => state.set_pointer_up(position),
+                KeyPress::Blur => state.release_all(),
             },
         }
     }
-}
-
-pub fn add_click_handler(elem: HtmlElement) -> UnboundedReceiver<()> {
-    let (mut click_sender, click_receiver) = unbounded();
-    let on_click = browser::closure_wrap(Box::new(move || {
-        if let Err(err) = click_sender.start_send(()) {
-            error!("Could not send click event {:#?}", err);
-        }
-    }) as Box<dyn FnMut()>);
-    elem.set_onclick(Some(on_click.as_ref().unchecked_ref()));
-    on_click.forget();
-    click_receiver
-}
-
-#[allow(dead_code)]
-unsafe fn draw_frame_rate(renderer: &Renderer, frame_time: f64) {
-    static mut FRAMES_COUNTED: i32 = 0;
-    static mut TOTAL_FRAME_TIME: f64 = 0.0;
-    static mut FRAME_RATE: i32 = 0;
-
-    FRAMES_COUNTED += 1;
-    TOTAL_FRAME_TIME += frame_time;
-
-    if TOTAL_FRAME_TIME > 1000.0 {
-        FRAME_RATE = FRAMES_COUNTED;
-        TOTAL_FRAME_TIME = 0.0;
-        FRAMES_COUNTED = 0;
-    }
-
-    if let Err(err) = renderer.draw_text(
-        &format!("Frame rate: {}", FRAME_RATE),
-        &Point { x: 400, y: 100 },
-    ) {
-        error!("Could not draw text {:#?}", err);
-    }
+    // Browsers without gamepad support simply report no buttons
+    state.set_gamepad_buttons(&browser::gamepad_buttons().unwrap_or_default());
 }
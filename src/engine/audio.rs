@@ -1,11 +1,36 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+};
+
 use anyhow::{Ok, Result};
-use web_sys::{AudioBuffer, AudioContext};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::Closure;
+use web_sys::{AudioBuffer, AudioBufferSourceNode, AudioContext, AudioContextState, GainNode};
 
 use crate::{browser, sound};
 
+const UNLOCK_EVENTS: [&str; 3] = ["keydown", "pointerdown", "touchstart"];
+
 #[derive(Clone)]
 pub struct Audio {
     context: AudioContext,
+    mixer: Rc<Mixer>,
+    unlock: Rc<Unlock>,
+}
+
+/// Browsers keep a new `AudioContext` suspended until the first user gesture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnlockState {
+    Locked,
+    Unlocking,
+    Unlocked,
+}
+
+struct Unlock {
+    state: Cell<UnlockState>,
+    pending: RefCell<Vec<Playback>>,
+    listener: RefCell<Option<Closure<dyn FnMut()>>>,
 }
 
 #[derive(Clone)]
@@ -13,11 +38,172 @@ pub struct Sound {
     buffer: AudioBuffer,
 }
 
+#[derive(Clone, Copy)]
+pub enum Channel {
+    Master,
+    Music,
+    Sfx,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AudioSettings {
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            master_volume: 1.0,
+            music_volume: 0.6,
+            sfx_volume: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    pub fn volume(&self, channel: Channel) -> f32 {
+        match channel {
+            Channel::Master => self.master_volume,
+            Channel::Music => self.music_volume,
+            Channel::Sfx => self.sfx_volume,
+        }
+    }
+
+    pub fn set_volume(&mut self, channel: Channel, volume: f32) {
+        let volume = volume.clamp(0.0, 1.0);
+        match channel {
+            Channel::Master => self.master_volume = volume,
+            Channel::Music => self.music_volume = volume,
+            Channel::Sfx => self.sfx_volume = volume,
+        }
+    }
+
+    fn master_gain(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.master_volume
+        }
+    }
+}
+
+/// Music and sound effects each go through their own bus before the master gain.
+struct Mixer {
+    master: GainNode,
+    music: GainNode,
+    sfx: GainNode,
+    settings: RefCell<AudioSettings>,
+}
+
+impl Mixer {
+    fn new(context: &AudioContext, settings: AudioSettings) -> Result<Self> {
+        let master = sound::create_gain_node(context)?;
+        let music = sound::create_gain_node(context)?;
+        let sfx = sound::create_gain_node(context)?;
+        sound::connect_with_audio_node(&master, &context.destination())?;
+        sound::connect_with_audio_node(&music, &master)?;
+        sound::connect_with_audio_node(&sfx, &master)?;
+
+        let mixer = Mixer {
+            master,
+            music,
+            sfx,
+            settings: RefCell::new(settings),
+        };
+        mixer.apply(context)?;
+        Ok(mixer)
+    }
+
+    fn apply(&self, context: &AudioContext) -> Result<()> {
+        let settings = self.settings.borrow();
+        sound::set_gain(context, &self.master, settings.master_gain())?;
+        sound::set_gain(context, &self.music, settings.music_volume)?;
+        sound::set_gain(context, &self.sfx, settings.sfx_volume)
+    }
+
+    fn bus(&self, channel: Channel) -> &GainNode {
+        match channel {
+            Channel::Master => &self.master,
+            Channel::Music => &self.music,
+            Channel::Sfx => &self.sfx,
+        }
+    }
+}
+
 impl Audio {
-    pub fn new() -> Result<Self> {
-        Ok(Audio {
-            context: sound::create_audio_context()?,
-        })
+    pub fn new(settings: AudioSettings) -> Result<Self> {
+        let context = sound::create_audio_context()?;
+        let mixer = Rc::new(Mixer::new(&context, settings)?);
+        let state = if context.state() == AudioContextState::Running {
+            UnlockState::Unlocked
+        } else {
+            UnlockState::Locked
+        };
+        let audio = Audio {
+            context,
+            mixer,
+            unlock: Rc::new(Unlock {
+                state: Cell::new(state),
+                pending: RefCell::new(vec![]),
+                listener: RefCell::new(None),
+            }),
+        };
+        if state == UnlockState::Locked {
+            audio.listen_for_unlock()?;
+        }
+        Ok(audio)
+    }
+
+    pub fn unlock_state(&self) -> UnlockState {
+        self.unlock.state.get()
+    }
+
+    fn listen_for_unlock(&self) -> Result<()> {
+        let audio = self.clone();
+        let listener = browser::closure_wrap(Box::new(move || {
+            audio.resume();
+        }) as Box<dyn FnMut()>);
+        for event in UNLOCK_EVENTS {
+            browser::add_window_listener(event, &listener)?;
+        }
+        self.unlock.listener.replace(Some(listener));
+        Ok(())
+    }
+
+    fn resume(&self) {
+        if self.unlock.state.get() != UnlockState::Locked {
+            return;
+        }
+        self.unlock.state.set(UnlockState::Unlocking);
+        let audio = self.clone();
+        browser::spawn_local(async move {
+            if let Err(err) = sound::resume(&audio.context).await {
+                error!("Could not resume the audio context {:#?}", err);
+                audio.unlock.state.set(UnlockState::Locked);
+            } else {
+                audio.unlocked();
+            }
+        });
+    }
+
+    fn unlocked(&self) {
+        self.unlock.state.set(UnlockState::Unlocked);
+        if let Some(listener) = self.unlock.listener.take() {
+            for event in UNLOCK_EVENTS {
+                if let Err(err) = browser::remove_window_listener(event, &listener) {
+                    error!("Could not remove audio unlock listener {:#?}", err);
+                }
+            }
+        }
+        for playback in self.unlock.pending.take() {
+            if let Err(err) = playback.resume() {
+                error!("Could not start queued sound {:#?}", err);
+            }
+        }
     }
 
     pub async fn load_sound(&self, resource: &str) -> Result<Sound> {
@@ -29,11 +215,265 @@ impl Audio {
         })
     }
 
-    pub fn play_sound(&self, sound: &Sound) -> Result<()> {
-        sound::play_sound(&self.context, &sound.buffer, sound::Looping::No)
+    /// Plays a one-shot effect, or nothing while the context is still locked.
+    pub fn play_sound(&self, sound: &Sound) -> Result<Playback> {
+        Playback::start(self, sound, Channel::Sfx, sound::Looping::No)
+    }
+
+    /// Plays looping music, from the unlock if the context is still locked.
+    pub fn play_loop(&self, sound: &Sound) -> Result<Playback> {
+        Playback::start(self, sound, Channel::Music, sound::Looping::Yes)
+    }
+
+    /// Applies new volumes straight away. Storing them is up to the caller.
+    pub fn set_settings(&self, settings: AudioSettings) -> Result<()> {
+        self.mixer.settings.replace(settings);
+        self.mixer.apply(&self.context)
+    }
+}
+
+/// Handle to a started sound. Clones refer to the same playback.
+#[derive(Clone)]
+pub struct Playback {
+    state: Rc<RefCell<PlaybackState>>,
+}
+
+struct PlaybackState {
+    this: Weak<RefCell<PlaybackState>>,
+    context: AudioContext,
+    buffer: AudioBuffer,
+    gain: GainNode,
+    looping: bool,
+    rate: f32,
+    source: Option<AudioBufferSourceNode>,
+    started_at: f64,
+    start_offset: f64,
+    paused_offset: Option<f64>,
+}
+
+impl Playback {
+    fn start(
+        audio: &Audio,
+        sound: &Sound,
+        channel: Channel,
+        looping: sound::Looping,
+    ) -> Result<Self> {
+        let gain = sound::create_gain_node(&audio.context)?;
+        sound::connect_with_audio_node(&gain, audio.mixer.bus(channel))?;
+        let state = Rc::new_cyclic(|this| {
+            RefCell::new(PlaybackState {
+                this: this.clone(),
+                context: audio.context.clone(),
+                buffer: sound.buffer.clone(),
+                gain,
+                looping: matches!(looping, sound::Looping::Yes),
+                rate: 1.0,
+                source: None,
+                started_at: 0.0,
+                start_offset: 0.0,
+                paused_offset: None,
+            })
+        });
+        let playback = Playback { state };
+        if audio.unlock_state() == UnlockState::Unlocked {
+            playback.state.borrow_mut().start_at(0.0)?;
+        } else if playback.state.borrow().looping {
+            // Queued music is paused at its start until the context resumes
+            playback.state.borrow_mut().paused_offset = Some(0.0);
+            audio.unlock.pending.borrow_mut().push(playback.clone());
+        }
+        // One-shot effects would all go off at once when the context resumes, so they stay idle
+        Ok(playback)
+    }
+
+    #[allow(dead_code)]
+    pub fn stop(&self) -> Result<()> {
+        let mut state = self.state.borrow_mut();
+        state.paused_offset = None;
+        let now = state.context.current_time();
+        state.stop_at(now)
+    }
+
+    #[allow(dead_code)]
+    pub fn pause(&self) -> Result<()> {
+        let mut state = self.state.borrow_mut();
+        if state.source.is_some() {
+            state.paused_offset = Some(state.position());
+            let now = state.context.current_time();
+            state.stop_at(now)?;
+        }
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn resume(&self) -> Result<()> {
+        let mut state = self.state.borrow_mut();
+        match state.paused_offset.take() {
+            Some(offset) => state.start_at(offset),
+            None => Ok(()),
+        }
+    }
+
+    pub fn set_playback_rate(&self, rate: f32) {
+        let mut state = self.state.borrow_mut();
+        state.start_offset = state.position();
+        state.started_at = state.context.current_time();
+        state.rate = rate;
+        if let Some(source) = &state.source {
+            source.playback_rate().set_value(rate);
+        }
+    }
+
+    pub fn set_volume(&self, volume: f32) -> Result<()> {
+        let state = self.state.borrow();
+        sound::set_gain(&state.context, &state.gain, volume)
+    }
+
+    pub fn fade_in(&self, duration: f64) -> Result<()> {
+        let state = self.state.borrow();
+        sound::ramp_gain(&state.context, &state.gain, 0.0, 1.0, duration)
+    }
+
+    #[allow(dead_code)]
+    pub fn fade_to(&self, volume: f32, duration: f64) -> Result<()> {
+        let state = self.state.borrow();
+        let current = state.gain.gain().value();
+        sound::ramp_gain(&state.context, &state.gain, current, volume, duration)
+    }
+
+    /// Fades the sound to silence, then stops it.
+    pub fn fade_out(&self, duration: f64) -> Result<()> {
+        let mut state = self.state.borrow_mut();
+        let current = state.gain.gain().value();
+        sound::ramp_gain(&state.context, &state.gain, current, 0.0, duration)?;
+        state.paused_offset = None;
+        let end = state.context.current_time() + duration;
+        state.stop_at(end)
+    }
+
+    #[allow(dead_code)]
+    pub fn is_playing(&self) -> bool {
+        self.state.borrow().source.is_some()
+    }
+}
+
+impl PlaybackState {
+    fn start_at(&mut self, offset: f64) -> Result<()> {
+        let looping = if self.looping {
+            sound::Looping::Yes
+        } else {
+            sound::Looping::No
+        };
+        let source = sound::play_sound(&self.context, &self.buffer, &self.gain, looping, offset)?;
+        source.playback_rate().set_value(self.rate);
+        let this = self.this.clone();
+        let node = source.clone();
+        let on_ended = Closure::once_into_js(move || {
+            if let Some(state) = this.upgrade() {
+                let mut state = state.borrow_mut();
+                // A stopped source ends after a newer one may have replaced it
+                if state.source.as_ref() == Some(&node) {
+                    state.source = None;
+                }
+            }
+        });
+        sound::on_ended(&source, &on_ended);
+        self.source = Some(source);
+        self.started_at = self.context.current_time();
+        self.start_offset = offset;
+        Ok(())
+    }
+
+    fn stop_at(&mut self, when: f64) -> Result<()> {
+        match self.source.take() {
+            Some(source) => sound::stop_sound(&source, when),
+            None => Ok(()),
+        }
+    }
+
+    fn position(&self) -> f64 {
+        let elapsed = (self.context.current_time() - self.started_at) * f64::from(self.rate);
+        buffer_position(
+            self.start_offset + elapsed,
+            self.buffer.duration(),
+            self.looping,
+        )
+    }
+}
+
+fn buffer_position(elapsed: f64, duration: f64, looping: bool) -> f64 {
+    if looping && duration > 0.0 {
+        elapsed % duration
+    } else {
+        elapsed.min(duration)
+    }
+}
+
+/// Plays one music track at a time, crossfading whenever the track changes.
+pub struct MusicController {
+    audio: Audio,
+    current: Option<(String, Playback)>,
+}
+
+impl MusicController {
+    pub fn new(audio: Audio) -> Self {
+        MusicController {
+            audio,
+            current: None,
+        }
+    }
+
+    pub fn crossfade_to(&mut self, name: &str, sound: &Sound, duration: f64) -> Result<()> {
+        if matches!(&self.current, Some((current, _)) if current == name) {
+            return Ok(());
+        }
+        if let Some((_, playback)) = self.current.take() {
+            playback.fade_out(duration)?;
+        }
+        let playback = self.audio.play_loop(sound)?;
+        playback.fade_in(duration)?;
+        self.current = Some((name.to_string(), playback));
+        Ok(())
+    }
+
+    pub fn stop(&mut self, duration: f64) -> Result<()> {
+        match self.current.take() {
+            Some((_, playback)) => playback.fade_out(duration),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volumes_are_clamped_to_unit_range() {
+        let mut settings = AudioSettings::default();
+
+        settings.set_volume(Channel::Music, 1.5);
+        settings.set_volume(Channel::Sfx, -0.5);
+
+        assert_eq!(settings.volume(Channel::Music), 1.0);
+        assert_eq!(settings.volume(Channel::Sfx), 0.0);
+    }
+
+    #[test]
+    fn muting_silences_the_master_bus_without_losing_its_volume() {
+        let settings = AudioSettings {
+            master_volume: 0.8,
+            muted: true,
+            ..AudioSettings::default()
+        };
+
+        assert_eq!(settings.master_gain(), 0.0);
+        assert_eq!(settings.volume(Channel::Master), 0.8);
     }
 
-    pub fn play_loop(&self, sound: &Sound) -> Result<()> {
-        sound::play_sound(&self.context, &sound.buffer, sound::Looping::Yes)
+    #[test]
+    fn looping_position_wraps_around_the_buffer() {
+        assert_eq!(buffer_position(12.5, 10.0, true), 2.5);
+        assert_eq!(buffer_position(12.5, 10.0, false), 10.0);
     }
 }
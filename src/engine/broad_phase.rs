@@ -0,0 +1,212 @@
+use super::Rect;
+
+/// Sort and sweep along x: bounds are sorted by their left edge so only rects
+/// whose x ranges overlap are ever compared. Narrow-phase checks then run on
+/// the candidates alone.
+pub struct BroadPhase {
+    entries: Vec<(Rect, usize)>,
+}
+
+impl BroadPhase {
+    /// Indexes each rect by its position in `bounds`.
+    pub fn new(bounds: impl IntoIterator<Item = Rect>) -> Self {
+        let mut entries: Vec<(Rect, usize)> = bounds
+            .into_iter()
+            .enumerate()
+            .map(|(index, bounds)| (bounds, index))
+            .collect();
+        entries.sort_by_key(|(bounds, _)| bounds.x());
+        BroadPhase { entries }
+    }
+
+    /// Indexes of every rect overlapping `area`, in left-to-right order.
+    pub fn query<'a>(&'a self, area: &'a Rect) -> impl Iterator<Item = usize> + 'a {
+        self.entries
+            .iter()
+            .take_while(move |(bounds, _)| bounds.x() < area.right())
+            .filter(move |(bounds, _)| bounds.intersects(area))
+            .map(|(_, index)| *index)
+    }
+
+    /// Every pair of overlapping rects, each with the lower index first.
+    #[allow(dead_code)]
+    pub fn pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = vec![];
+        let mut active: Vec<&(Rect, usize)> = vec![];
+        for entry in &self.entries {
+            let (bounds, index) = entry;
+            active.retain(|(other, _)| other.right() > bounds.x());
+            pairs.extend(
+                active
+                    .iter()
+                    .filter(|(other, _)| other.intersects(bounds))
+                    .map(|(_, other_index)| (*other_index.min(index), *other_index.max(index))),
+            );
+            active.push(entry);
+        }
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::engine::vector::Vector;
+
+    const OBSTACLES: usize = 16;
+
+    fn random_bounds(count: usize, world_width: i32) -> Vec<Rect> {
+        let mut rng = StdRng::seed_from_u64(7);
+        (0..count)
+            .map(|_| {
+                Rect::new_from_x_y(
+                    rng.gen_range(0..world_width),
+                    rng.gen_range(0..600),
+                    rng.gen_range(10..120),
+                    rng.gen_range(10..120),
+                )
+            })
+            .collect()
+    }
+
+    fn naive_pairs(bounds: &[Rect]) -> Vec<(usize, usize)> {
+        let mut pairs = vec![];
+        for (index, rect) in bounds.iter().enumerate() {
+            for (other_index, other) in bounds.iter().enumerate().skip(index + 1) {
+                if rect.intersects(other) {
+                    pairs.push((index, other_index));
+                }
+            }
+        }
+        pairs
+    }
+
+    #[test]
+    fn query_returns_only_overlapping_rects() {
+        let broad_phase = BroadPhase::new(vec![
+            Rect::new_from_x_y(300, 0, 50, 50),
+            Rect::new_from_x_y(0, 0, 50, 50),
+            Rect::new_from_x_y(40, 200, 50, 50),
+            Rect::new_from_x_y(60, 10, 50, 50),
+        ]);
+
+        let candidates: Vec<usize> = broad_phase
+            .query(&Rect::new_from_x_y(30, 20, 40, 40))
+            .collect();
+
+        assert_eq!(candidates, vec![1, 3]);
+    }
+
+    #[test]
+    fn pairs_match_the_naive_loop() {
+        let bounds = random_bounds(300, 3000);
+
+        let mut pairs = BroadPhase::new(bounds.clone()).pairs();
+        pairs.sort();
+
+        assert_eq!(pairs, naive_pairs(&bounds));
+    }
+
+    #[test]
+    fn touching_rects_are_not_paired() {
+        let broad_phase = BroadPhase::new(vec![
+            Rect::new_from_x_y(0, 0, 10, 10),
+            Rect::new_from_x_y(10, 0, 10, 10),
+        ]);
+
+        assert!(broad_phase.pairs().is_empty());
+    }
+
+    /// Platforms made of a few boxes each, spread over a couple of screens as
+    /// segments are in game.
+    fn random_obstacles(count: usize) -> Vec<Vec<Rect>> {
+        let mut rng = StdRng::seed_from_u64(11);
+        (0..count)
+            .map(|index| {
+                let x = index as i32 * 2000 / count as i32;
+                let y = rng.gen_range(100..500);
+                (0..rng.gen_range(1..=3))
+                    .map(|part| Rect::new_from_x_y(x + part * 60, y, 60, 30))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn obstacle_bounds(obstacle: &[Rect]) -> Rect {
+        obstacle
+            .iter()
+            .copied()
+            .reduce(|bounds, bounding_box| bounds.union(&bounding_box))
+            .unwrap_or_default()
+    }
+
+    /// The narrow phase a platform runs: the earliest hit over its boxes.
+    fn first_hit(boy: &Rect, velocity: Vector, obstacle: &[Rect]) -> Option<f32> {
+        obstacle
+            .iter()
+            .filter_map(|bounding_box| boy.sweep(velocity, bounding_box))
+            .map(|hit| hit.time)
+            .min_by(f32::total_cmp)
+    }
+
+    /// Times the walking collision pass a frame at a time: querying the broad
+    /// phase for the boy's swept box and running the narrow phase on its
+    /// candidates, rebuilding the index as often as segments arrive, against
+    /// running the narrow phase on every obstacle. Run with
+    /// `cargo test --release -- --ignored` to check the broad phase still pays off.
+    #[test]
+    #[ignore]
+    fn frame_collisions_beat_checking_every_obstacle() {
+        const FRAMES: i32 = 100_000;
+        const FRAMES_PER_SEGMENT: i32 = 60;
+        let obstacles = random_obstacles(OBSTACLES);
+        let velocity = Vector::new(4.0, 6.0);
+        let boy_at = |frame: i32| Rect::new_from_x_y(frame % 2000, 380, 80, 100);
+
+        let start = Instant::now();
+        let mut every_hit = 0;
+        for frame in 0..FRAMES {
+            let boy = boy_at(frame);
+            every_hit += obstacles
+                .iter()
+                .filter(|obstacle| first_hit(&boy, velocity, obstacle).is_some())
+                .count();
+        }
+        let every_time = start.elapsed() / FRAMES as u32;
+
+        let start = Instant::now();
+        let mut broad_hit = 0;
+        let mut broad_phase = BroadPhase::new(vec![]);
+        for frame in 0..FRAMES {
+            if frame % FRAMES_PER_SEGMENT == 0 {
+                broad_phase =
+                    BroadPhase::new(obstacles.iter().map(|obstacle| obstacle_bounds(obstacle)));
+            }
+            let boy = boy_at(frame);
+            let swept_box = boy.union(&Rect::new_from_x_y(
+                boy.x() + velocity.x as i32,
+                boy.y() + velocity.y as i32,
+                boy.width,
+                boy.height,
+            ));
+            broad_hit += broad_phase
+                .query(&swept_box)
+                .filter(|index| first_hit(&boy, velocity, &obstacles[*index]).is_some())
+                .count();
+        }
+        let broad_time = start.elapsed() / FRAMES as u32;
+
+        assert_eq!(every_hit, broad_hit);
+        assert!(
+            broad_time < every_time,
+            "{} obstacles: broad phase took {:?} a frame, checking every obstacle {:?}",
+            OBSTACLES,
+            broad_time,
+            every_time
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use super::{Point, Vector};
+
+/// Maps world coordinates to the screen. The camera eases towards a target placed
+/// at `anchor` on screen, shifted forward by the target's velocity.
+pub struct Camera {
+    position: Vector,
+    anchor: Point,
+    look_ahead: f32,
+    smoothing: f32,
+    vertical_dead_zone: i32,
+    min_y: i32,
+    shake: Point,
+}
+
+impl Camera {
+    pub fn new(anchor: Point, look_ahead: f32, smoothing: f32) -> Self {
+        Camera {
+            position: Vector::ZERO,
+            anchor,
+            look_ahead,
+            smoothing,
+            vertical_dead_zone: 0,
+            min_y: 0,
+            shake: Point::default(),
+        }
+    }
+
+    /// Lets the camera rise up to `min_y` when the target goes above `dead_zone`
+    /// pixels from the top of the screen.
+    pub fn follow_vertically(&mut self, dead_zone: i32, min_y: i32) {
+        self.vertical_dead_zone = dead_zone;
+        self.min_y = min_y;
+    }
+
+    pub fn follow(&mut self, target: Vector, velocity: Vector) {
+        let target = self.target_position(target, velocity);
+        self.position = self.position.lerp(target, self.smoothing);
+    }
+
+    pub fn snap_to(&mut self, target: Vector, velocity: Vector) {
+        self.position = self.target_position(target, velocity);
+    }
+
+    pub fn position(&self) -> Point {
+        self.position.to_point()
+    }
+
+    /// The position things are drawn from, including any screen shake. Gameplay
+    /// keeps using `position` so shaking never moves the world.
+    pub fn view(&self) -> Point {
+        self.position() + self.shake
+    }
+
+    pub fn set_shake(&mut self, shake: Point) {
+        self.shake = shake;
+    }
+
+    pub fn left(&self) -> i32 {
+        self.position().x
+    }
+
+    #[allow(dead_code)]
+    pub fn world_to_screen(&self, point: Point) -> Point {
+        point - self.position()
+    }
+
+    fn target_position(&self, target: Vector, velocity: Vector) -> Vector {
+        let x = target.x - self.anchor.x as f32 + velocity.x * self.look_ahead;
+        let y = (target.y - self.vertical_dead_zone as f32).clamp(self.min_y as f32, 0.0);
+        Vector::new(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANCHOR: Point = Point { x: 40, y: 0 };
+
+    #[test]
+    fn snapping_puts_the_target_on_its_anchor() {
+        let mut camera = Camera::new(ANCHOR, 0.0, 0.1);
+
+        camera.snap_to(Vector::new(500.0, 400.0), Vector::default());
+
+        assert_eq!(
+            camera.world_to_screen(Point { x: 500, y: 400 }),
+            Point { x: 40, y: 400 }
+        );
+    }
+
+    #[test]
+    fn looking_ahead_moves_the_target_back_on_screen() {
+        let mut camera = Camera::new(ANCHOR, 20.0, 0.1);
+
+        camera.snap_to(Vector::default(), Vector::new(3.0, 0.0));
+
+        assert_eq!(camera.left(), 20);
+    }
+
+    #[test]
+    fn following_eases_towards_the_target() {
+        let mut camera = Camera::new(ANCHOR, 0.0, 0.5);
+
+        camera.follow(Vector::new(140.0, 0.0), Vector::default());
+
+        assert_eq!(camera.left(), 50);
+    }
+
+    #[test]
+    fn vertical_follow_only_happens_above_the_dead_zone() {
+        let mut camera = Camera::new(ANCHOR, 0.0, 1.0);
+        camera.follow_vertically(60, -100);
+
+        camera.follow(Vector::new(0.0, 300.0), Vector::default());
+        assert_eq!(camera.position().y, 0);
+
+        camera.follow(Vector::new(0.0, 20.0), Vector::default());
+        assert_eq!(camera.position().y, -40);
+
+        camera.follow(Vector::new(0.0, -200.0), Vector::default());
+        assert_eq!(camera.position().y, -100);
+    }
+
+    #[test]
+    fn shaking_only_moves_the_view() {
+        let mut camera = Camera::new(ANCHOR, 0.0, 1.0);
+        camera.snap_to(Vector::new(140.0, 0.0), Vector::default());
+
+        camera.set_shake(Point { x: 3, y: -2 });
+
+        assert_eq!(camera.position(), Point { x: 100, y: 0 });
+        assert_eq!(camera.view(), Point { x: 103, y: -2 });
+    }
+}
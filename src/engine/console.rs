@@ -0,0 +1,142 @@
+use std::{cell::RefCell, collections::VecDeque};
+
+use anyhow::{anyhow, Result};
+use futures::channel::oneshot::{channel, Receiver, Sender};
+
+pub const CONSOLE_KEY: &str = "Backquote";
+
+pub type Run<T> = fn(&mut T, &[&str]) -> Result<String>;
+
+pub struct Command<T> {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub help: &'static str,
+    pub run: Run<T>,
+}
+
+/// Named commands that act on a `T`, such as the game world, run from a line
+/// of text like `set gravity 0.5`.
+pub struct Commands<T> {
+    commands: Vec<Command<T>>,
+}
+
+impl<T> Commands<T> {
+    pub fn new(commands: Vec<Command<T>>) -> Self {
+        Commands { commands }
+    }
+
+    /// Runs the command named by the first word with the rest as arguments.
+    /// `help` lists every command.
+    pub fn run(&self, target: &mut T, line: &str) -> Result<String> {
+        let mut words = line.split_whitespace();
+        let name = words
+            .next()
+            .ok_or_else(|| anyhow!("Type a command, or help"))?;
+        let arguments: Vec<&str> = words.collect();
+        if name == "help" {
+            return Ok(self.help().join("\n"));
+        }
+        let command = self
+            .commands
+            .iter()
+            .find(|command| command.name == name)
+            .ok_or_else(|| anyhow!("Unknown command '{}', try help", name))?;
+        (command.run)(target, &arguments)
+            .map_err(|err| err.context(format!("Usage: {} {}", command.name, command.usage)))
+    }
+
+    pub fn help(&self) -> Vec<String> {
+        self.commands
+            .iter()
+            .map(|command| format!("{} {}: {}", command.name, command.usage, command.help))
+            .collect()
+    }
+}
+
+/// Parses the argument at `index`, naming it in the error.
+pub fn argument<A: std::str::FromStr>(arguments: &[&str], index: usize, name: &str) -> Result<A> {
+    let argument = arguments
+        .get(index)
+        .ok_or_else(|| anyhow!("Missing {}", name))?;
+    argument
+        .parse()
+        .map_err(|_| anyhow!("'{}' is not a valid {}", argument, name))
+}
+
+/// What a command printed, or why it failed.
+pub type Output = std::result::Result<String, String>;
+
+/// A line from JavaScript and where its output goes.
+pub struct Submitted {
+    pub line: String,
+    pub reply: Sender<Output>,
+}
+
+thread_local! {
+    // Lines from JavaScript, which has no handle on the running game
+    static SUBMITTED: RefCell<VecDeque<Submitted>> = const { RefCell::new(VecDeque::new()) };
+}
+
+/// Queues a command line for the game to run on its next update, returning
+/// where its output will arrive.
+pub fn submit(line: &str) -> Receiver<Output> {
+    let (reply, output) = channel();
+    SUBMITTED.with(|submitted| {
+        submitted.borrow_mut().push_back(Submitted {
+            line: line.to_string(),
+            reply,
+        })
+    });
+    output
+}
+
+pub fn take_submitted() -> Vec<Submitted> {
+    SUBMITTED.with(|submitted| submitted.borrow_mut().drain(..).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands() -> Commands<i32> {
+        Commands::new(vec![Command {
+            name: "add",
+            usage: "<amount>",
+            help: "adds to the total",
+            run: |total, arguments| {
+                *total += argument::<i32>(arguments, 0, "amount")?;
+                Ok(format!("Total is {}", total))
+            },
+        }])
+    }
+
+    #[test]
+    fn commands_run_with_their_arguments() {
+        let mut total = 1;
+
+        let output = commands().run(&mut total, "  add   41 ").unwrap();
+
+        assert_eq!(total, 42);
+        assert_eq!(output, "Total is 42");
+        assert_eq!(
+            commands().run(&mut total, "help").unwrap(),
+            "add <amount>: adds to the total"
+        );
+    }
+
+    #[test]
+    fn bad_lines_explain_themselves() {
+        let mut total = 0;
+
+        let unknown = commands().run(&mut total, "subtract 1").unwrap_err();
+        let invalid = commands().run(&mut total, "add lots").unwrap_err();
+
+        assert_eq!(unknown.to_string(), "Unknown command 'subtract', try help");
+        assert_eq!(invalid.to_string(), "Usage: add <amount>");
+        assert_eq!(
+            invalid.root_cause().to_string(),
+            "'lots' is not a valid amount"
+        );
+        assert_eq!(total, 0);
+    }
+}
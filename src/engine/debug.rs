@@ -0,0 +1,120 @@
+use anyhow::Result;
+
+use super::{Point, Rect, Renderer};
+
+pub const DEBUG_KEY: &str = "F3";
+
+const PANEL_LEFT: i32 = 10;
+const PANEL_BOTTOM: i32 = 590;
+// Wide enough for log lines
+const PANEL_WIDTH: i32 = 580;
+const PADDING: i32 = 10;
+const FRAME_RATE_POSITION: Point = Point { x: 400, y: 100 };
+const LINE_HEIGHT: i32 = 16;
+const FONT: &str = "12px monospace";
+const TEXT_COLOR: &str = "white";
+const PANEL_COLOR: &str = "black";
+const PANEL_ALPHA: f32 = 0.6;
+const SAMPLE_PERIOD: f64 = 1000.0;
+
+/// Frames counted over each second.
+#[derive(Debug, Default)]
+struct FrameRate {
+    frames: u32,
+    elapsed: f64,
+    frame_rate: u32,
+}
+
+impl FrameRate {
+    fn record_frame(&mut self, frame_time: f64) {
+        self.frames += 1;
+        self.elapsed += frame_time;
+        if self.elapsed > SAMPLE_PERIOD {
+            *self = FrameRate {
+                frame_rate: self.frames,
+                ..FrameRate::default()
+            };
+        }
+    }
+}
+
+/// The F3 overlay, owned by the game loop. While it is open the renderer also
+/// outlines colliders.
+#[derive(Debug, Default)]
+pub struct DebugOverlay {
+    enabled: bool,
+    frame_rate: FrameRate,
+}
+
+impl DebugOverlay {
+    pub fn new() -> Self {
+        DebugOverlay::default()
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn frame_rate(&self) -> u32 {
+        self.frame_rate.frame_rate
+    }
+
+    pub fn record_frame(&mut self, frame_time: f64) {
+        self.frame_rate.record_frame(frame_time);
+    }
+
+    /// Draws the frame rate followed by `lines`, or just the frame rate when
+    /// `show_frame_rate` is set and the overlay is closed.
+    pub fn draw(&self, renderer: &Renderer, show_frame_rate: bool, lines: &[String]) -> Result<()> {
+        if !self.enabled {
+            if show_frame_rate {
+                renderer.draw_text(
+                    &format!("Frame rate: {}", self.frame_rate()),
+                    &FRAME_RATE_POSITION,
+                )?;
+            }
+            return Ok(());
+        }
+
+        // Grows up from the bottom of the canvas to fit every line
+        let height = (lines.len() as i32 + 1) * LINE_HEIGHT + 2 * PADDING;
+        let panel = Rect::new_from_x_y(PANEL_LEFT, PANEL_BOTTOM - height, PANEL_WIDTH, height);
+        renderer.fill_rect(&panel, PANEL_COLOR, PANEL_ALPHA);
+        let frame_rate = format!("FPS: {}", self.frame_rate());
+        std::iter::once(&frame_rate)
+            .chain(lines)
+            .enumerate()
+            .try_for_each(|(index, line)| {
+                let position = Point {
+                    x: panel.x() + PADDING,
+                    y: panel.y() + PADDING + index as i32 * LINE_HEIGHT,
+                };
+                renderer.draw_styled_text(line, &position, FONT, TEXT_COLOR)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_are_counted_over_each_second() {
+        let mut overlay = DebugOverlay::new();
+
+        for _ in 0..60 {
+            overlay.record_frame(16.0);
+        }
+        assert_eq!(overlay.frame_rate(), 0);
+
+        for _ in 0..3 {
+            overlay.record_frame(16.0);
+        }
+        assert_eq!(overlay.frame_rate(), 63);
+        assert_eq!(overlay.frame_rate.frames, 0);
+    }
+}
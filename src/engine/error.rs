@@ -0,0 +1,106 @@
+use std::{fmt, panic::PanicHookInfo};
+
+use wasm_bindgen::JsValue;
+
+use super::logging;
+use crate::browser;
+
+const REPORT_LOG_LINES: usize = 50;
+
+#[derive(Debug)]
+pub enum EngineError {
+    Browser { action: String, cause: String },
+    Missing(&'static str),
+    ElementNotFound(String),
+    Conversion { value: String, target: &'static str },
+    Resource { resource: String, cause: String },
+    MissingSprite(String),
+}
+
+impl EngineError {
+    pub fn browser(action: impl Into<String>, cause: JsValue) -> Self {
+        EngineError::Browser {
+            action: action.into(),
+            cause: format!("{:?}", cause),
+        }
+    }
+
+    pub fn conversion(value: impl fmt::Debug, target: &'static str) -> Self {
+        EngineError::Conversion {
+            value: format!("{:?}", value),
+            target,
+        }
+    }
+
+    pub fn resource(resource: &str, cause: impl fmt::Display) -> Self {
+        EngineError::Resource {
+            resource: resource.into(),
+            cause: cause.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::Browser { action, cause } => write!(f, "Could not {}: {}", action, cause),
+            EngineError::Missing(name) => write!(f, "No {} found", name),
+            EngineError::ElementNotFound(id) => write!(f, "Element with id '{}' not found", id),
+            EngineError::Conversion { value, target } => {
+                write!(f, "Could not convert {} to {}", value, target)
+            }
+            EngineError::Resource { resource, cause } => {
+                write!(f, "Could not load resource {}: {}", resource, cause)
+            }
+            EngineError::MissingSprite(name) => write!(f, "Sprite cell '{}' not found", name),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+/// Logs the error and replaces the game with an overlay listing the error chain.
+pub fn show_error_screen(err: &anyhow::Error) {
+    error!("{:?}", err);
+    let messages = err.chain().map(ToString::to_string).collect::<Vec<_>>();
+    draw_overlay(&messages);
+}
+
+pub fn show_panic_screen(info: &PanicHookInfo) {
+    draw_overlay(&[info.to_string()]);
+}
+
+fn draw_overlay(messages: &[String]) {
+    let log = logging::recent(REPORT_LOG_LINES);
+    if let Err(err) = browser::draw_error_overlay(messages, &log) {
+        error!("Could not draw the error screen {:#?}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn error_chain_keeps_engine_error_as_root_cause() {
+        let err = Err::<(), _>(EngineError::MissingSprite("Run (1).png".into()))
+            .context("Error while drawing the game")
+            .unwrap_err();
+
+        let messages = err.chain().map(ToString::to_string).collect::<Vec<_>>();
+
+        assert_eq!(
+            messages,
+            vec![
+                "Error while drawing the game".to_string(),
+                "Sprite cell 'Run (1).png' not found".to_string()
+            ]
+        );
+        assert!(matches!(
+            err.root_cause().downcast_ref::<EngineError>(),
+            Some(EngineError::MissingSprite(_))
+        ));
+    }
+}
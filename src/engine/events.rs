@@ -0,0 +1,48 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+/// A queue shared by everything that publishes events during an update and the
+/// systems that react to them afterwards.
+pub struct EventQueue<E> {
+    events: Rc<RefCell<VecDeque<E>>>,
+}
+
+impl<E> EventQueue<E> {
+    pub fn new() -> Self {
+        EventQueue {
+            events: Rc::new(RefCell::new(VecDeque::new())),
+        }
+    }
+
+    pub fn publish(&self, event: E) {
+        self.events.borrow_mut().push_back(event);
+    }
+
+    pub fn drain(&self) -> Vec<E> {
+        self.events.borrow_mut().drain(..).collect()
+    }
+}
+
+impl<E> Clone for EventQueue<E> {
+    fn clone(&self) -> Self {
+        EventQueue {
+            events: Rc::clone(&self.events),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_the_same_queue() {
+        let queue = EventQueue::new();
+        let publisher = queue.clone();
+
+        publisher.publish(1);
+        publisher.publish(2);
+
+        assert_eq!(queue.drain(), vec![1, 2]);
+        assert!(queue.drain().is_empty());
+    }
+}
@@ -1,15 +1,31 @@
 use std::{cell::RefCell, rc::Rc};
 
-use anyhow::{anyhow, Result};
+use anyhow::{Context, Result};
 
 use crate::browser::{self, LoopClosure};
 
-use super::{draw_frame_rate, prepare_input, process_input, KeyState, Renderer, FRAME_SIZE};
+use super::{
+    debug::{DebugOverlay, DEBUG_KEY},
+    error, logging, prepare_input, process_input,
+    profiler::{Profiler, TRACE_KEY},
+    KeyState, Renderer, FRAME_SIZE,
+};
+
+const OVERLAY_LOG_LINES: usize = 5;
 
 pub trait Game {
     async fn initialize(&self) -> Result<Box<impl Game + 'static>>;
-    fn update(&mut self, keystate: &KeyState);
+    fn update(&mut self, keystate: &KeyState) -> Result<()>;
     fn draw(&self, renderer: &Renderer) -> Result<()>;
+
+    fn show_frame_rate(&self) -> bool {
+        false
+    }
+
+    /// Extra lines for the debug overlay.
+    fn debug_info(&self) -> Vec<String> {
+        vec![]
+    }
 }
 
 pub struct GameLoop {
@@ -20,47 +36,94 @@ pub struct GameLoop {
 type SharedLoopClosure = Rc<RefCell<Option<LoopClosure>>>;
 
 impl GameLoop {
-    pub async fn start(game: impl Game + 'static) -> Result<()> {
+    /// Runs `game`, timing the loop with `profiler`, which the game can share
+    /// to time its own systems.
+    pub async fn start(game: impl Game + 'static, profiler: Profiler) -> Result<()> {
         let mut keyevent_receiver = prepare_input()?;
         let mut keystate = super::KeyState::new();
-        let mut game = game.initialize().await?;
+        let mut game = game
+            .initialize()
+            .await
+            .context("Could not initialize the game")?;
         let mut game_loop = GameLoop {
             last_frame: browser::now()?,
             accumulated_delta: 0.0,
         };
 
         let renderer = Renderer::new(browser::context()?);
+        let mut debug_overlay = DebugOverlay::new();
 
         let f: SharedLoopClosure = Rc::new(RefCell::new(None));
         let g = Rc::clone(&f);
         *g.borrow_mut() = Some(browser::create_raf_closure(move |perf| {
-            process_input(&mut keystate, &mut keyevent_receiver);
+            // Release builds only pay for timing while the overlay shows it
+            profiler.set_recording(cfg!(debug_assertions) || debug_overlay.is_enabled());
+            let frame_scope = profiler.scope("frame");
+            {
+                let _scope = profiler.scope("input");
+                process_input(&mut keystate, &mut keyevent_receiver);
+            }
             let frame_time = perf - game_loop.last_frame;
             game_loop.accumulated_delta += frame_time as f32;
             while game_loop.accumulated_delta > FRAME_SIZE {
-                game.update(&keystate);
+                if keystate.is_just_pressed(DEBUG_KEY) {
+                    debug_overlay.toggle();
+                }
+                if keystate.is_just_pressed(TRACE_KEY) {
+                    if let Err(err) = profiler.export_trace() {
+                        error!("Could not export the trace {:#?}", err);
+                    }
+                }
+                let update_scope = profiler.scope("update");
+                if let Err(err) = game
+                    .update(&keystate)
+                    .context("Error while updating the game")
+                {
+                    error::show_error_screen(&err);
+                    return;
+                }
+                drop(update_scope);
+                keystate.clear_just_pressed();
                 game_loop.accumulated_delta -= FRAME_SIZE;
             }
             game_loop.last_frame = perf;
-            if let Err(err) = game.draw(&renderer) {
-                error!("Error while drawing the game: {:#?}", err);
+            renderer.set_debug(debug_overlay.is_enabled());
+            let draw_scope = profiler.scope("draw");
+            if let Err(err) = game.draw(&renderer).context("Error while drawing the game") {
+                error::show_error_screen(&err);
+                return;
             }
+            drop(draw_scope);
+            drop(frame_scope);
+            debug_overlay.record_frame(frame_time);
 
-            if cfg!(debug_assertions) {
-                unsafe {
-                    draw_frame_rate(&renderer, frame_time);
-                }
+            let debug_info = if debug_overlay.is_enabled() {
+                let mut lines = profiler.summary();
+                lines.extend(game.debug_info());
+                lines.extend(logging::recent(OVERLAY_LOG_LINES));
+                lines
+            } else {
+                vec![]
+            };
+            if let Err(err) = debug_overlay.draw(&renderer, game.show_frame_rate(), &debug_info) {
+                error!("Could not draw the debug overlay {:#?}", err);
             }
 
-            if let Err(err) = browser::request_animation_frame(f.borrow().as_ref().unwrap()) {
-                error!("Unable to request animation frame: {:#?}", err);
+            let result = f
+                .borrow()
+                .as_ref()
+                .ok_or(error::EngineError::Missing("game loop closure"))
+                .map_err(anyhow::Error::from)
+                .and_then(browser::request_animation_frame);
+            if let Err(err) = result {
+                error::show_error_screen(&err.context("Unable to request animation frame"));
             }
         }));
 
         browser::request_animation_frame(
             g.borrow()
                 .as_ref()
-                .ok_or_else(|| anyhow!("GameLoop: Loop is None"))?,
+                .ok_or(error::EngineError::Missing("game loop closure"))?,
         )?;
         Ok(())
     }
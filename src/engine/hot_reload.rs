@@ -0,0 +1,188 @@
+use std::{cell::Cell, rc::Rc};
+
+use anyhow::Result;
+use web_sys::HtmlImageElement;
+
+use futures::future::LocalBoxFuture;
+
+use crate::browser;
+
+use super::{error::EngineError, KeyState, SpriteSheet};
+
+pub const RELOAD_KEY: &str = "F6";
+/// Game data loaded from files, such as level layouts, that can be fetched
+/// again while the game runs.
+pub trait Reload {
+    /// Every file the data is read from.
+    fn resources(&self) -> Vec<String>;
+    /// Replaces the data only once every file has loaded.
+    fn reload(&self) -> LocalBoxFuture<'_, Result<()>>;
+}
+
+/// Page query naming a dev server endpoint to poll for changed assets.
+const ENDPOINT_QUERY: &str = "hot_reload";
+const POLL_INTERVAL: f64 = 1000.0;
+
+#[derive(Clone)]
+enum Asset {
+    SpriteSheet(Rc<SpriteSheet>),
+    Image {
+        resource: String,
+        element: HtmlImageElement,
+    },
+    Data(Rc<dyn Reload>),
+}
+
+impl Asset {
+    fn is_any_of(&self, changed: &[String]) -> bool {
+        let resources = match self {
+            Asset::SpriteSheet(sheet) => sheet.resources().map(ToString::to_string).to_vec(),
+            Asset::Image { resource, .. } => vec![resource.clone()],
+            Asset::Data(data) => data.resources(),
+        };
+        resources
+            .iter()
+            .any(|resource| changed.iter().any(|path| same_resource(resource, path)))
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Asset::SpriteSheet(sheet) => sheet.resources()[0].to_string(),
+            Asset::Image { resource, .. } => resource.clone(),
+            Asset::Data(data) => data.resources().join(", "),
+        }
+    }
+
+    async fn reload(&self) -> Result<()> {
+        match self {
+            Asset::SpriteSheet(sheet) => sheet.reload().await,
+            Asset::Image { resource, element } => super::reload_image(element, resource).await,
+            Asset::Data(data) => data.reload().await,
+        }
+    }
+}
+
+/// Development mode asset reloading. Watched assets are fetched again in place
+/// when `RELOAD_KEY` is pressed, or when the endpoint in the `hot_reload` page
+/// query reports them changed, so the current run carries on with the new art and data.
+///
+/// The endpoint is polled every second and should answer with a JSON array of
+/// the paths that changed since its last answer, like
+/// `["assets/sprite_sheets/tiles.json"]`.
+pub struct HotReload {
+    assets: Vec<Asset>,
+    endpoint: Option<String>,
+    last_poll: f64,
+    // Only one reload or poll at a time, so answers can't arrive out of order
+    busy: Rc<Cell<bool>>,
+}
+
+impl HotReload {
+    /// Only debug builds reload, so release builds never poll.
+    pub fn new() -> Self {
+        let endpoint = if cfg!(debug_assertions) {
+            browser::query_param(ENDPOINT_QUERY).unwrap_or_else(|err| {
+                error!("Could not read the hot reload endpoint {:#?}", err);
+                None
+            })
+        } else {
+            None
+        };
+        HotReload {
+            assets: vec![],
+            endpoint,
+            last_poll: 0.0,
+            busy: Rc::new(Cell::new(false)),
+        }
+    }
+
+    pub fn watch_sprite_sheet(&mut self, sheet: &Rc<SpriteSheet>) {
+        self.assets.push(Asset::SpriteSheet(sheet.clone()));
+    }
+
+    pub fn watch_image(&mut self, resource: &str, element: &HtmlImageElement) {
+        self.assets.push(Asset::Image {
+            resource: resource.to_string(),
+            element: element.clone(),
+        });
+    }
+
+    pub fn watch_data(&mut self, data: Rc<dyn Reload>) {
+        self.assets.push(Asset::Data(data));
+    }
+
+    pub fn update(&mut self, keystate: &KeyState) {
+        if !cfg!(debug_assertions) || self.busy.get() {
+            return;
+        }
+        if keystate.is_just_pressed(RELOAD_KEY) {
+            self.start(None, self.assets.clone());
+            return;
+        }
+        if let Some(endpoint) = &self.endpoint {
+            let now = browser::now().unwrap_or_default();
+            if now - self.last_poll > POLL_INTERVAL {
+                self.last_poll = now;
+                self.start(Some(endpoint.clone()), self.assets.clone());
+            }
+        }
+    }
+
+    /// Reloads `assets` in the background, or only those `endpoint` reports changed.
+    fn start(&self, endpoint: Option<String>, assets: Vec<Asset>) {
+        let busy = self.busy.clone();
+        busy.set(true);
+        browser::spawn_local(async move {
+            if let Err(err) = reload(endpoint, assets).await {
+                error!("Could not hot reload assets {:#}", err);
+            }
+            busy.set(false);
+        });
+    }
+}
+
+async fn reload(endpoint: Option<String>, assets: Vec<Asset>) -> Result<()> {
+    let assets = match endpoint {
+        Some(endpoint) => {
+            let changed: Vec<String> = serde_wasm_bindgen::from_value(
+                browser::fetch_json(&browser::cache_busted(&endpoint)?).await?,
+            )
+            .map_err(|err| EngineError::resource(&endpoint, err))?;
+            assets
+                .into_iter()
+                .filter(|asset| asset.is_any_of(&changed))
+                .collect()
+        }
+        None => assets,
+    };
+    let mut reloaded = 0;
+    for asset in &assets {
+        // Files can be missing or half written while they are being saved
+        match asset.reload().await {
+            Ok(()) => reloaded += 1,
+            Err(err) => warn!("Keeping the old {}: {:#}", asset.name(), err),
+        }
+    }
+    if reloaded > 0 {
+        log!("Reloaded {} assets", reloaded);
+    }
+    Ok(())
+}
+
+/// Dev servers may report paths from the site root, with or without a leading slash.
+fn same_resource(resource: &str, path: &str) -> bool {
+    resource.trim_start_matches("./").trim_start_matches('/')
+        == path.trim_start_matches("./").trim_start_matches('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_match_with_or_without_a_leading_slash() {
+        assert!(same_resource("assets/tiles.json", "/assets/tiles.json"));
+        assert!(same_resource("./assets/tiles.png", "assets/tiles.png"));
+        assert!(!same_resource("assets/tiles.json", "assets/tiles.png"));
+    }
+}
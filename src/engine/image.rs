@@ -1,42 +1,39 @@
 use anyhow::Result;
 use web_sys::HtmlImageElement;
 
-use super::{Point, Rect, Renderer};
+use super::{Rect, Renderer, Vector};
 
+/// An image at a sub-pixel position, drawn and collided at the nearest pixel.
 pub struct Image {
     element: HtmlImageElement,
+    position: Vector,
     bounding_box: Rect,
 }
 
 impl Image {
-    pub fn new(element: HtmlImageElement, position: Point) -> Self {
-        let bounding_box = Rect::new(position, element.width() as i16, element.height() as i16);
+    pub fn new(element: HtmlImageElement, position: Vector) -> Self {
+        let bounding_box = Rect::new(
+            position.to_point(),
+            element.width() as i32,
+            element.height() as i32,
+        );
         Image {
             element,
+            position,
             bounding_box,
         }
     }
 
+    pub fn position(&self) -> Vector {
+        self.position
+    }
+
     pub fn draw(&self, renderer: &Renderer) -> Result<()> {
-        if cfg!(debug_assertions) {
-            renderer.draw_rect(&self.bounding_box);
-        }
+        renderer.draw_debug_rect(&self.bounding_box);
         renderer.draw_entire_image(&self.element, &self.bounding_box.position)
     }
 
     pub fn bounding_box(&self) -> &Rect {
         &self.bounding_box
     }
-
-    pub fn right(&self) -> i16 {
-        self.bounding_box.right()
-    }
-
-    pub fn move_horizontally(&mut self, dx: i16) {
-        self.bounding_box.set_x(self.bounding_box.x() + dx);
-    }
-
-    pub fn set_x(&mut self, x: i16) {
-        self.bounding_box.set_x(x);
-    }
 }
@@ -0,0 +1,153 @@
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+
+use super::{Point, Rect, Renderer};
+
+const MAX_SHAKE: f32 = 12.0;
+const TRAUMA_DECAY: f32 = 0.02;
+const FLASH_DECAY: f32 = 0.08;
+const FLASH_COLOR: &str = "white";
+
+/// Each effect can be turned off for players sensitive to motion or flashing.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JuiceSettings {
+    pub screen_shake: bool,
+    pub hit_stop: bool,
+    pub flash: bool,
+}
+
+impl Default for JuiceSettings {
+    fn default() -> Self {
+        JuiceSettings {
+            screen_shake: true,
+            hit_stop: true,
+            flash: true,
+        }
+    }
+}
+
+/// Trauma-based screen shake, hit-stop and full screen flashes.
+pub struct Juice {
+    settings: JuiceSettings,
+    trauma: f32,
+    shake: Point,
+    hit_stop_frames: u8,
+    flash: f32,
+}
+
+impl Juice {
+    pub fn new(settings: JuiceSettings) -> Self {
+        Juice {
+            settings,
+            trauma: 0.0,
+            shake: Point::default(),
+            hit_stop_frames: 0,
+            flash: 0.0,
+        }
+    }
+
+    /// Effects already under way finish; new ones follow `settings`.
+    pub fn set_settings(&mut self, settings: JuiceSettings) {
+        self.settings = settings;
+    }
+
+    pub fn add_trauma(&mut self, amount: f32) {
+        if self.settings.screen_shake {
+            self.trauma = (self.trauma + amount).min(1.0);
+        }
+    }
+
+    pub fn hit_stop(&mut self, frames: u8) {
+        if self.settings.hit_stop {
+            self.hit_stop_frames = self.hit_stop_frames.max(frames);
+        }
+    }
+
+    pub fn flash(&mut self, alpha: f32) {
+        if self.settings.flash {
+            self.flash = self.flash.max(alpha);
+        }
+    }
+
+    /// Returns true while updates should be frozen, counting down one frame per call.
+    pub fn consume_hit_stop(&mut self) -> bool {
+        if self.hit_stop_frames > 0 {
+            self.hit_stop_frames -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn update(&mut self) {
+        let intensity = self.trauma * self.trauma * MAX_SHAKE;
+        let mut rng = thread_rng();
+        self.shake = Point {
+            x: (rng.gen_range(-1.0..=1.0) * intensity).round() as i32,
+            y: (rng.gen_range(-1.0..=1.0) * intensity).round() as i32,
+        };
+        self.trauma = (self.trauma - TRAUMA_DECAY).max(0.0);
+        self.flash = (self.flash - FLASH_DECAY).max(0.0);
+    }
+
+    pub fn shake(&self) -> Point {
+        self.shake
+    }
+
+    pub fn draw(&self, renderer: &Renderer, screen: &Rect) {
+        if self.flash > 0.0 {
+            renderer.fill_rect(screen, FLASH_COLOR, self.flash);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hit_stop_freezes_for_the_requested_frames() {
+        let mut juice = Juice::new(JuiceSettings::default());
+
+        juice.hit_stop(2);
+
+        assert!(juice.consume_hit_stop());
+        assert!(juice.consume_hit_stop());
+        assert!(!juice.consume_hit_stop());
+    }
+
+    #[test]
+    fn disabled_effects_are_ignored() {
+        let mut juice = Juice::new(JuiceSettings {
+            screen_shake: false,
+            hit_stop: false,
+            flash: false,
+        });
+
+        juice.add_trauma(1.0);
+        juice.hit_stop(5);
+        juice.flash(1.0);
+        juice.update();
+
+        assert_eq!(juice.shake(), Point::default());
+        assert!(!juice.consume_hit_stop());
+        assert_eq!(juice.flash, 0.0);
+    }
+
+    #[test]
+    fn trauma_wears_off() {
+        let mut juice = Juice::new(JuiceSettings::default());
+        juice.add_trauma(1.0);
+
+        // Any one shake can round to nothing, but not several in a row at full trauma
+        let shook = (0..3).any(|_| {
+            juice.update();
+            juice.shake() != Point::default()
+        });
+        (0..(1.0 / TRAUMA_DECAY).ceil() as usize).for_each(|_| juice.update());
+
+        assert!(shook);
+        assert_eq!(juice.trauma, 0.0);
+        assert_eq!(juice.shake(), Point::default());
+    }
+}
@@ -1,26 +1,143 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
 
+use super::Point;
+
+/// Key codes reported for gamepad buttons, so they can be checked like keys.
+pub const GAMEPAD_A: &str = "GamepadA";
+pub const GAMEPAD_B: &str = "GamepadB";
+pub const GAMEPAD_START: &str = "GamepadStart";
+pub const GAMEPAD_UP: &str = "GamepadUp";
+pub const GAMEPAD_DOWN: &str = "GamepadDown";
+pub const GAMEPAD_LEFT: &str = "GamepadLeft";
+pub const GAMEPAD_RIGHT: &str = "GamepadRight";
+
+/// Standard gamepad mapping indexes for the buttons above.
+const GAMEPAD_BUTTONS: [(usize, &str); 7] = [
+    (0, GAMEPAD_A),
+    (1, GAMEPAD_B),
+    (9, GAMEPAD_START),
+    (12, GAMEPAD_UP),
+    (13, GAMEPAD_DOWN),
+    (14, GAMEPAD_LEFT),
+    (15, GAMEPAD_RIGHT),
+];
+
+/// The pointer in canvas coordinates. The `just_` flags last for one update.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PointerState {
+    pub position: Point,
+    pub down: bool,
+    pub just_pressed: bool,
+    pub just_released: bool,
+}
+
+/// Everything the player is pressing: keys, gamepad buttons and the pointer.
 pub struct KeyState {
-    pressed_keys: HashMap<String, web_sys::KeyboardEvent>,
+    pressed_keys: HashSet<String>,
+    just_pressed_keys: HashSet<String>,
+    pointer: PointerState,
 }
 
 impl KeyState {
     pub fn new() -> Self {
         KeyState {
-            pressed_keys: HashMap::new(),
+            pressed_keys: HashSet::new(),
+            just_pressed_keys: HashSet::new(),
+            pointer: PointerState::default(),
         }
     }
 
     pub fn is_pressed(&self, code: &str) -> bool {
-        self.pressed_keys.contains_key(code)
+        self.pressed_keys.contains(code)
+    }
+
+    /// True only for the first update after the key went down, ignoring auto-repeat.
+    pub fn is_just_pressed(&self, code: &str) -> bool {
+        self.just_pressed_keys.contains(code)
+    }
+
+    /// Keys and buttons that went down this update, for capturing key bindings.
+    pub fn just_pressed_keys(&self) -> impl Iterator<Item = &str> {
+        self.just_pressed_keys.iter().map(String::as_str)
     }
 
-    pub fn set_pressed(&mut self, code: &str, event: web_sys::KeyboardEvent) {
-        log!("Key pressed: {}", code);
-        self.pressed_keys.insert(code.into(), event);
+    pub fn pointer(&self) -> &PointerState {
+        &self.pointer
+    }
+
+    pub fn set_pressed(&mut self, code: &str) {
+        if self.pressed_keys.insert(code.into()) {
+            self.just_pressed_keys.insert(code.into());
+        }
     }
 
     pub fn set_released(&mut self, code: &str) {
         self.pressed_keys.remove(code);
     }
+
+    /// Forgets every held key, for when key ups can no longer reach the canvas.
+    pub fn release_all(&mut self) {
+        self.pressed_keys.clear();
+    }
+
+    /// Takes the pressed state of every button on the gamepad, as returned by
+    /// `browser::gamepad_buttons`.
+    pub fn set_gamepad_buttons(&mut self, buttons: &[bool]) {
+        for (index, code) in GAMEPAD_BUTTONS {
+            let pressed = buttons.get(index).copied().unwrap_or(false);
+            if pressed && !self.is_pressed(code) {
+                self.set_pressed(code);
+            } else if !pressed {
+                self.set_released(code);
+            }
+        }
+    }
+
+    pub fn set_pointer_position(&mut self, position: Point) {
+        self.pointer.position = position;
+    }
+
+    pub fn set_pointer_down(&mut self, position: Point) {
+        self.pointer.position = position;
+        self.pointer.down = true;
+        self.pointer.just_pressed = true;
+    }
+
+    pub fn set_pointer_up(&mut self, position: Point) {
+        self.pointer.position = position;
+        self.pointer.down = false;
+        self.pointer.just_released = true;
+    }
+
+    pub fn clear_just_pressed(&mut self) {
+        self.just_pressed_keys.clear();
+        self.pointer.just_pressed = false;
+        self.pointer.just_released = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gamepad_buttons_are_pressed_like_keys() {
+        let mut keystate = KeyState::new();
+        let mut buttons = vec![false; 16];
+        buttons[0] = true;
+        buttons[12] = true;
+
+        keystate.set_gamepad_buttons(&buttons);
+
+        assert!(keystate.is_just_pressed(GAMEPAD_A));
+        assert!(keystate.is_pressed(GAMEPAD_UP));
+        assert!(!keystate.is_pressed(GAMEPAD_DOWN));
+
+        keystate.clear_just_pressed();
+        keystate.set_gamepad_buttons(&buttons);
+        assert!(!keystate.is_just_pressed(GAMEPAD_A));
+
+        keystate.set_gamepad_buttons(&[]);
+        assert!(!keystate.is_pressed(GAMEPAD_A));
+    }
 }
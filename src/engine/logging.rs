@@ -0,0 +1,257 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fmt::{self, Arguments},
+};
+
+const RECENT_CAPACITY: usize = 200;
+const DEFAULT_LEVEL: Level = Level::Info;
+const FILTER_QUERY: &str = "log";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn parse(name: &str) -> Option<Option<Level>> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "off" => Some(None),
+            "error" => Some(Some(Level::Error)),
+            "warn" => Some(Some(Level::Warn)),
+            "info" => Some(Some(Level::Info)),
+            "debug" => Some(Some(Level::Debug)),
+            "trace" => Some(Some(Level::Trace)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        };
+        f.pad(name)
+    }
+}
+
+/// Which levels each target logs, parsed from directives like
+/// `warn,engine::audio=debug,game=off`. A bare level sets the default, and the
+/// longest matching target prefix wins. Targets are module paths without the
+/// crate name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Filter {
+    default: Option<Level>,
+    targets: Vec<(String, Option<Level>)>,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter {
+            default: Some(DEFAULT_LEVEL),
+            targets: vec![],
+        }
+    }
+}
+
+impl Filter {
+    /// Directives that can't be parsed are skipped with a warning.
+    pub fn parse(directives: &str) -> Self {
+        let mut filter = Filter::default();
+        for directive in directives.split(',').filter(|d| !d.trim().is_empty()) {
+            let parsed = match directive.split_once('=') {
+                Some((target, level)) => Level::parse(level).map(|level| {
+                    filter.targets.push((target.trim().to_string(), level));
+                }),
+                None => Level::parse(directive).map(|level| filter.default = level),
+            };
+            if parsed.is_none() {
+                warn!("Ignoring log directive '{}'", directive);
+            }
+        }
+        // Longest first, so the first match is the most specific
+        filter
+            .targets
+            .sort_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+        filter
+    }
+
+    pub fn enabled(&self, level: Level, target: &str) -> bool {
+        let max = self
+            .targets
+            .iter()
+            .find(|(prefix, _)| matches_target(target, prefix))
+            .map_or(self.default, |(_, level)| *level);
+        max.is_some_and(|max| level <= max)
+    }
+}
+
+fn matches_target(target: &str, prefix: &str) -> bool {
+    target == prefix
+        || target
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with("::"))
+}
+
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub time: f64,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>8.1}s {:<5} {}: {}",
+            self.time / 1000.0,
+            self.level,
+            self.target,
+            self.message
+        )
+    }
+}
+
+#[derive(Default)]
+struct Logger {
+    filter: Filter,
+    recent: VecDeque<Record>,
+}
+
+thread_local! {
+    // The log macros can be called from anywhere, so the logger can't be passed around
+    static LOGGER: RefCell<Logger> = RefCell::new(Logger::default());
+}
+
+pub fn set_filter(filter: Filter) {
+    LOGGER.with(|logger| logger.borrow_mut().filter = filter);
+}
+
+/// Filters with the `log` query parameter when the page has one, otherwise
+/// with `directives` from the settings.
+pub fn configure(directives: &str) {
+    let directives = crate::browser::query_param(FILTER_QUERY)
+        .unwrap_or_else(|err| {
+            error!("Could not read the log filter from the URL {:#?}", err);
+            None
+        })
+        .unwrap_or_else(|| directives.to_string());
+    set_filter(Filter::parse(&directives));
+    log!("Logging with filter '{}'", directives);
+}
+
+pub fn enabled(level: Level, module_path: &str) -> bool {
+    LOGGER.with(|logger| logger.borrow().filter.enabled(level, target(module_path)))
+}
+
+/// Used by the log macros, which check `enabled` first.
+pub fn log(level: Level, module_path: &str, message: Arguments) {
+    let record = Record {
+        time: timestamp(),
+        level,
+        target: target(module_path).to_string(),
+        message: message.to_string(),
+    };
+    write_to_console(&record);
+    LOGGER.with(|logger| {
+        let recent = &mut logger.borrow_mut().recent;
+        if recent.len() == RECENT_CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(record);
+    });
+}
+
+/// The last `count` records, oldest first, for bug reports and the debug overlay.
+pub fn recent(count: usize) -> Vec<String> {
+    LOGGER.with(|logger| {
+        // The panic screen asks for these, possibly while a log call holds the logger
+        let Ok(logger) = logger.try_borrow() else {
+            return vec![];
+        };
+        let recent = &logger.recent;
+        recent
+            .iter()
+            .skip(recent.len().saturating_sub(count))
+            .map(ToString::to_string)
+            .collect()
+    })
+}
+
+fn target(module_path: &str) -> &str {
+    module_path
+        .split_once("::")
+        .map_or(module_path, |(_, target)| target)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn timestamp() -> f64 {
+    crate::browser::now().unwrap_or_default()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn timestamp() -> f64 {
+    0.0
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_to_console(record: &Record) {
+    let line = wasm_bindgen::JsValue::from_str(&record.to_string());
+    match record.level {
+        Level::Error => web_sys::console::error_1(&line),
+        Level::Warn => web_sys::console::warn_1(&line),
+        Level::Info => web_sys::console::info_1(&line),
+        Level::Debug | Level::Trace => web_sys::console::debug_1(&line),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_to_console(record: &Record) {
+    eprintln!("{}", record);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_most_specific_target_wins() {
+        let filter = Filter::parse("warn, engine=debug,engine::audio=off,game::music=trace");
+
+        assert!(filter.enabled(Level::Warn, "game"));
+        assert!(!filter.enabled(Level::Info, "game::hud"));
+        assert!(filter.enabled(Level::Debug, "engine::key_state"));
+        assert!(!filter.enabled(Level::Error, "engine::audio"));
+        assert!(filter.enabled(Level::Trace, "game::music"));
+        assert!(!filter.enabled(Level::Trace, "game::musician"));
+    }
+
+    #[test]
+    fn bad_directives_keep_the_defaults() {
+        assert_eq!(Filter::parse("loud,audio=maybe"), Filter::default());
+    }
+
+    #[test]
+    fn only_recent_records_are_kept() {
+        for index in 0..RECENT_CAPACITY + 5 {
+            log(Level::Info, "walk_the_dog::game", format_args!("{}", index));
+        }
+
+        let recent = recent(2);
+
+        assert_eq!(recent.len(), 2);
+        assert!(recent[0].ends_with("game: 203"));
+        assert!(recent[1].ends_with("game: 204"));
+    }
+}
@@ -0,0 +1,106 @@
+use anyhow::Result;
+use web_sys::HtmlImageElement;
+
+use super::{camera::Camera, Point, Renderer};
+
+/// An image repeated horizontally that scrolls at a fraction of the camera speed.
+pub struct ParallaxLayer {
+    image: HtmlImageElement,
+    scroll_factor: f32,
+    y: i32,
+    spacing: i32,
+}
+
+impl ParallaxLayer {
+    pub fn new(image: HtmlImageElement, scroll_factor: f32, y: i32, spacing: i32) -> Self {
+        ParallaxLayer {
+            image,
+            scroll_factor,
+            y,
+            spacing,
+        }
+    }
+
+    fn tile_width(&self) -> i32 {
+        self.image.width() as i32 + self.spacing
+    }
+
+    fn draw(&self, renderer: &Renderer, camera: &Camera, width: i32) -> Result<()> {
+        let camera = camera.view();
+        let y = self.y - (camera.y as f32 * self.scroll_factor).round() as i32;
+        tile_positions(-self.scrolled_x(camera.x), self.tile_width(), width)
+            .try_for_each(|x| renderer.draw_entire_image(&self.image, &Point { x, y }))
+    }
+
+    /// Wraps at the tile width to keep the scaled offset small however far the camera goes.
+    fn scrolled_x(&self, camera_x: i32) -> i32 {
+        let tile_width = self.tile_width().max(1) as f32;
+        ((camera_x as f32 * self.scroll_factor) % tile_width).round() as i32
+    }
+}
+
+/// Layers are drawn in the order they were added, so add the farthest first.
+pub struct Parallax {
+    layers: Vec<ParallaxLayer>,
+    width: i32,
+}
+
+impl Parallax {
+    pub fn new(width: i32) -> Self {
+        Parallax {
+            layers: vec![],
+            width,
+        }
+    }
+
+    pub fn add_layer(&mut self, layer: ParallaxLayer) {
+        self.layers.push(layer);
+    }
+
+    pub fn draw(&self, renderer: &Renderer, camera: &Camera) -> Result<()> {
+        self.layers
+            .iter()
+            .try_for_each(|layer| layer.draw(renderer, camera, self.width))
+    }
+}
+
+/// X positions of every tile copy needed to cover `0..width` once the layer has
+/// scrolled by `offset` (negative offsets move the tiles left).
+fn tile_positions(offset: i32, tile_width: i32, width: i32) -> impl Iterator<Item = i32> {
+    let tile_width = tile_width.max(1);
+    let first = offset.rem_euclid(tile_width) - tile_width;
+    (0..)
+        .map(move |index| first + index * tile_width)
+        .take_while(move |x| *x < width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_cover_the_whole_width() {
+        let positions = tile_positions(0, 250, 600).collect::<Vec<_>>();
+
+        assert_eq!(positions, vec![-250, 0, 250, 500]);
+    }
+
+    #[test]
+    fn scrolling_left_wraps_around_the_tile() {
+        let positions = tile_positions(-260, 250, 600).collect::<Vec<_>>();
+
+        assert_eq!(positions, vec![-10, 240, 490]);
+    }
+
+    #[test]
+    fn a_tile_wider_than_the_canvas_is_repeated_once_its_edge_shows() {
+        assert_eq!(
+            tile_positions(-100, 1000, 600).collect::<Vec<_>>(),
+            vec![-100]
+        );
+        assert_eq!(
+            tile_positions(-500, 1000, 600).collect::<Vec<_>>(),
+            vec![-500, 500]
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use std::{ops::RangeInclusive, rc::Rc};
+
+use anyhow::Result;
+use rand::{thread_rng, Rng};
+
+use super::{Point, Rect, Renderer, SpriteSheet, Vector};
+
+/// How a single particle is drawn.
+#[derive(Clone)]
+pub enum ParticleLook {
+    Square {
+        color: &'static str,
+        size: i32,
+    },
+    #[allow(dead_code)]
+    Sprite {
+        sheet: Rc<SpriteSheet>,
+        cell: String,
+    },
+}
+
+/// Describes what an emitter spawns. Velocities are in pixels per update and
+/// lifetimes in updates.
+#[derive(Clone)]
+pub struct EmitterConfig {
+    pub burst: usize,
+    pub velocity_x: RangeInclusive<f32>,
+    pub velocity_y: RangeInclusive<f32>,
+    pub gravity: f32,
+    pub lifetime: RangeInclusive<u16>,
+    pub fade: bool,
+    pub look: ParticleLook,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Particle {
+    position: Vector,
+    velocity: Vector,
+    age: u16,
+    lifetime: u16,
+}
+
+impl Particle {
+    fn is_alive(&self) -> bool {
+        self.age < self.lifetime
+    }
+
+    fn alpha(&self, fade: bool) -> f32 {
+        if fade {
+            1.0 - f32::from(self.age) / f32::from(self.lifetime.max(1))
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A fixed pool of particles. Bursts reuse dead particles and are cut short
+/// when the pool is full, so emitting never allocates.
+pub struct Emitter {
+    config: EmitterConfig,
+    particles: Vec<Particle>,
+}
+
+impl Emitter {
+    pub fn new(config: EmitterConfig, capacity: usize) -> Self {
+        Emitter {
+            config,
+            particles: vec![Particle::default(); capacity],
+        }
+    }
+
+    pub fn emit(&mut self, position: Point) {
+        let mut rng = thread_rng();
+        let config = &self.config;
+        self.particles
+            .iter_mut()
+            .filter(|particle| !particle.is_alive())
+            .take(config.burst)
+            .for_each(|particle| {
+                *particle = Particle {
+                    position: position.into(),
+                    velocity: Vector::new(
+                        rng.gen_range(config.velocity_x.clone()),
+                        rng.gen_range(config.velocity_y.clone()),
+                    ),
+                    age: 0,
+                    lifetime: rng.gen_range(config.lifetime.clone()),
+                }
+            });
+    }
+
+    pub fn update(&mut self) {
+        let gravity = Vector::new(0.0, self.config.gravity);
+        self.particles
+            .iter_mut()
+            .filter(|particle| particle.is_alive())
+            .for_each(|particle| {
+                particle.velocity += gravity;
+                particle.position += particle.velocity;
+                particle.age += 1;
+            });
+    }
+
+    #[allow(dead_code)]
+    pub fn live_particles(&self) -> usize {
+        self.particles
+            .iter()
+            .filter(|particle| particle.is_alive())
+            .count()
+    }
+
+    pub fn clear(&mut self) {
+        self.particles
+            .iter_mut()
+            .for_each(|particle| particle.age = particle.lifetime);
+    }
+
+    pub fn draw(&self, renderer: &Renderer) -> Result<()> {
+        self.particles
+            .iter()
+            .filter(|particle| particle.is_alive())
+            .try_for_each(|particle| self.draw_particle(renderer, particle))
+    }
+
+    fn draw_particle(&self, renderer: &Renderer, particle: &Particle) -> Result<()> {
+        let position = particle.position.to_point();
+        let alpha = particle.alpha(self.config.fade);
+        match &self.config.look {
+            ParticleLook::Square { color, size } => {
+                let rect =
+                    Rect::new_from_x_y(position.x - size / 2, position.y - size / 2, *size, *size);
+                renderer.fill_rect(&rect, color, alpha);
+                Ok(())
+            }
+            ParticleLook::Sprite { sheet, cell } => match sheet.cell(cell) {
+                Some(cell) => renderer.with_alpha(alpha, |renderer| {
+                    sheet.draw(renderer, &cell.frame(), &cell.destination(&position))
+                }),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(burst: usize) -> EmitterConfig {
+        EmitterConfig {
+            burst,
+            velocity_x: 1.0..=1.0,
+            velocity_y: 0.0..=0.0,
+            gravity: 0.5,
+            lifetime: 3..=3,
+            fade: true,
+            look: ParticleLook::Square {
+                color: "white",
+                size: 2,
+            },
+        }
+    }
+
+    #[test]
+    fn particles_die_after_their_lifetime() {
+        let mut emitter = Emitter::new(config(4), 8);
+
+        emitter.emit(Point::default());
+        assert_eq!(emitter.live_particles(), 4);
+
+        (0..3).for_each(|_| emitter.update());
+        assert_eq!(emitter.live_particles(), 0);
+    }
+
+    #[test]
+    fn bursts_are_capped_by_the_pool() {
+        let mut emitter = Emitter::new(config(4), 6);
+
+        emitter.emit(Point::default());
+        emitter.emit(Point::default());
+
+        assert_eq!(emitter.live_particles(), 6);
+    }
+
+    #[test]
+    fn particles_fall_and_fade() {
+        let mut emitter = Emitter::new(config(1), 1);
+        emitter.emit(Point { x: 10, y: 10 });
+
+        emitter.update();
+
+        let particle = emitter.particles[0];
+        assert_eq!(particle.position, Vector::new(11.0, 10.5));
+        assert!((particle.alpha(true) - 2.0 / 3.0).abs() < f32::EPSILON);
+    }
+}
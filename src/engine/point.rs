@@ -1,5 +1,33 @@
-#[derive(Clone, Copy, Default)]
+use std::ops::{Add, Sub};
+
+use serde::{Deserialize, Serialize};
+
+/// A whole pixel position. Float math happens on `Vector`, which converts from
+/// a `Point` and rounds back with `Vector::to_point`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Point {
-    pub x: i16,
-    pub y: i16,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Point {
+        Point {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, other: Point) -> Point {
+        Point {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
 }
@@ -0,0 +1,246 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::browser;
+
+pub const TRACE_KEY: &str = "F4";
+const TRACE_FILE: &str = "walk_the_dog.trace.json";
+/// About ten seconds of frames with a dozen markers each.
+const MAX_EVENTS: usize = 8000;
+const WINDOW: usize = 120;
+/// Upper bounds in milliseconds; the last bucket takes everything slower.
+const BUCKETS: [f64; 7] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One completed marker, in the Chrome trace event format ("X" events are
+/// complete events, with timestamps and durations in microseconds).
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TraceEvent {
+    name: &'static str,
+    ph: &'static str,
+    ts: f64,
+    dur: f64,
+    pid: u32,
+    tid: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Trace<'a> {
+    trace_events: &'a VecDeque<TraceEvent>,
+    display_time_unit: &'static str,
+}
+
+/// The most recent durations of one marker.
+#[derive(Debug, Default)]
+struct Histogram {
+    samples: VecDeque<f64>,
+}
+
+impl Histogram {
+    fn add(&mut self, duration: f64) {
+        if self.samples.len() == WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(duration);
+    }
+
+    fn average(&self) -> f64 {
+        self.samples.iter().sum::<f64>() / self.samples.len().max(1) as f64
+    }
+
+    fn percentile(&self, fraction: f64) -> f64 {
+        let mut sorted: Vec<f64> = self.samples.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        let index = ((sorted.len() as f64 * fraction).ceil() as usize).saturating_sub(1);
+        sorted.get(index).copied().unwrap_or_default()
+    }
+
+    fn buckets(&self) -> [usize; BUCKETS.len() + 1] {
+        let mut buckets = [0; BUCKETS.len() + 1];
+        for sample in &self.samples {
+            let bucket = BUCKETS
+                .iter()
+                .position(|bound| sample <= bound)
+                .unwrap_or(BUCKETS.len());
+            buckets[bucket] += 1;
+        }
+        buckets
+    }
+
+    /// The buckets as a row of bars, fastest on the left.
+    fn bars(&self) -> String {
+        let buckets = self.buckets();
+        let tallest = buckets.iter().copied().max().unwrap_or(0).max(1);
+        buckets
+            .iter()
+            .map(|count| match count {
+                0 => ' ',
+                count => BARS[(count * BARS.len()).div_ceil(tallest) - 1],
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Default)]
+struct ProfilerState {
+    recording: bool,
+    events: VecDeque<TraceEvent>,
+    // In the order markers were first seen, which keeps the overlay steady
+    histograms: Vec<(&'static str, Histogram)>,
+}
+
+/// Scoped timing markers, kept as a trace of recent events and a rolling
+/// histogram per marker. Clones share the same recording.
+#[derive(Clone, Debug, Default)]
+pub struct Profiler {
+    state: Rc<RefCell<ProfilerState>>,
+}
+
+impl Profiler {
+    /// Records from the start in debug builds only.
+    pub fn new() -> Self {
+        let profiler = Profiler::default();
+        profiler.set_recording(cfg!(debug_assertions));
+        profiler
+    }
+
+    /// While not recording, scopes cost nothing and record nothing.
+    pub fn set_recording(&self, recording: bool) {
+        self.state.borrow_mut().recording = recording;
+    }
+
+    /// Times from now until the returned guard is dropped.
+    pub fn scope(&self, name: &'static str) -> Scope {
+        let recording = self.state.borrow().recording;
+        Scope {
+            name,
+            started: recording.then(|| (self.clone(), browser::now().unwrap_or_default())),
+        }
+    }
+
+    /// Records a marker that ran from `start` to `end`, in milliseconds.
+    pub fn record(&self, name: &'static str, start: f64, end: f64) {
+        let duration = end - start;
+        let mut state = self.state.borrow_mut();
+        if state.events.len() == MAX_EVENTS {
+            state.events.pop_front();
+        }
+        state.events.push_back(TraceEvent {
+            name,
+            ph: "X",
+            ts: start * 1000.0,
+            dur: duration * 1000.0,
+            pid: 1,
+            tid: 1,
+        });
+        match state
+            .histograms
+            .iter_mut()
+            .find(|(marker, _)| *marker == name)
+        {
+            Some((_, histogram)) => histogram.add(duration),
+            None => {
+                let mut histogram = Histogram::default();
+                histogram.add(duration);
+                state.histograms.push((name, histogram));
+            }
+        }
+    }
+
+    /// One line per marker: average and 95th percentile in milliseconds, then
+    /// the histogram.
+    pub fn summary(&self) -> Vec<String> {
+        self.state
+            .borrow()
+            .histograms
+            .iter()
+            .map(|(name, histogram)| {
+                format!(
+                    "{:<12}{:>6.2}{:>6.2} {}",
+                    name,
+                    histogram.average(),
+                    histogram.percentile(0.95),
+                    histogram.bars()
+                )
+            })
+            .collect()
+    }
+
+    /// Downloads the recorded events for chrome://tracing or Perfetto.
+    pub fn export_trace(&self) -> Result<()> {
+        let state = self.state.borrow();
+        let json = browser::to_json(&Trace {
+            trace_events: &state.events,
+            display_time_unit: "ms",
+        })?;
+        browser::download(TRACE_FILE, &json)
+    }
+}
+
+pub struct Scope {
+    name: &'static str,
+    started: Option<(Profiler, f64)>,
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        if let Some((profiler, start)) = &self.started {
+            let end = browser::now().unwrap_or(*start);
+            profiler.record(self.name, *start, end);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markers_become_trace_events_and_histograms() {
+        let profiler = Profiler::new();
+
+        profiler.record("update", 10.0, 12.5);
+        profiler.record("draw", 12.5, 13.0);
+        profiler.record("update", 26.0, 27.0);
+
+        let state = profiler.state.borrow();
+        assert_eq!(state.events.len(), 3);
+        assert_eq!(state.events[0].ts, 10_000.0);
+        assert_eq!(state.events[0].dur, 2500.0);
+        let (name, update) = &state.histograms[0];
+        assert_eq!(*name, "update");
+        assert_eq!(update.average(), 1.75);
+        assert_eq!(update.buckets(), [0, 0, 1, 0, 1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn scopes_record_nothing_while_not_recording() {
+        let profiler = Profiler::new();
+        profiler.set_recording(false);
+
+        drop(profiler.scope("update"));
+
+        let state = profiler.state.borrow();
+        assert!(state.events.is_empty());
+        assert!(state.histograms.is_empty());
+    }
+
+    #[test]
+    fn histograms_only_keep_the_recent_window() {
+        let mut histogram = Histogram::default();
+        for _ in 0..WINDOW {
+            histogram.add(20.0);
+        }
+        for _ in 0..WINDOW - 10 {
+            histogram.add(1.0);
+        }
+
+        assert_eq!(histogram.samples.len(), WINDOW);
+        assert_eq!(histogram.percentile(0.5), 1.0);
+        assert_eq!(histogram.percentile(0.95), 20.0);
+        assert_eq!(histogram.bars(), "  █    ▁");
+    }
+}
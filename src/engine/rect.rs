@@ -1,14 +1,16 @@
-use super::Point;
+use serde::{Deserialize, Serialize};
 
-#[derive(Default)]
+use super::{Point, Vector};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Rect {
     pub position: Point,
-    pub width: i16,
-    pub height: i16,
+    pub width: i32,
+    pub height: i32,
 }
 
 impl Rect {
-    pub const fn new(position: Point, width: i16, height: i16) -> Self {
+    pub const fn new(position: Point, width: i32, height: i32) -> Self {
         Rect {
             position,
             width,
@@ -16,7 +18,7 @@ impl Rect {
         }
     }
 
-    pub const fn new_from_x_y(x: i16, y: i16, width: i16, height: i16) -> Self {
+    pub const fn new_from_x_y(x: i32, y: i32, width: i32, height: i32) -> Self {
         let position = Point { x, y };
         Rect {
             position,
@@ -25,15 +27,11 @@ impl Rect {
         }
     }
 
-    pub fn set_x(&mut self, x: i16) {
-        self.position.x = x;
-    }
-
-    pub fn x(&self) -> i16 {
+    pub fn x(&self) -> i32 {
         self.position.x
     }
 
-    pub fn y(&self) -> i16 {
+    pub fn y(&self) -> i32 {
         self.position.y
     }
 
@@ -44,13 +42,161 @@ impl Rect {
             && self.bottom() > other.y()
     }
 
-    pub fn right(&self) -> i16 {
+    pub fn right(&self) -> i32 {
         self.x() + self.width
     }
 
-    pub fn bottom(&self) -> i16 {
+    pub fn bottom(&self) -> i32 {
         self.y() + self.height
     }
+
+    /// The overlapping area of two rects, if they intersect.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if !self.intersects(other) {
+            return None;
+        }
+        let x = self.x().max(other.x());
+        let y = self.y().max(other.y());
+        Some(Rect::new_from_x_y(
+            x,
+            y,
+            self.right().min(other.right()) - x,
+            self.bottom().min(other.bottom()) - y,
+        ))
+    }
+
+    /// The smallest move that takes this rect out of `other`, along whichever
+    /// axis they overlap least.
+    #[allow(dead_code)]
+    pub fn minimum_translation(&self, other: &Rect) -> Option<Point> {
+        let overlap = self.intersection(other)?;
+        // Compare doubled centres to stay in whole pixels
+        let direction = |center: i32, other_center: i32| if center < other_center { -1 } else { 1 };
+        Some(if overlap.width < overlap.height {
+            Point {
+                x: direction(self.x() * 2 + self.width, other.x() * 2 + other.width)
+                    * overlap.width,
+                y: 0,
+            }
+        } else {
+            Point {
+                x: 0,
+                y: direction(self.y() * 2 + self.height, other.y() * 2 + other.height)
+                    * overlap.height,
+            }
+        })
+    }
+
+    /// Like the rect itself, includes the top and left edges but not the bottom and right.
+    pub fn contains_point(&self, point: &Point) -> bool {
+        point.x >= self.x()
+            && point.x < self.right()
+            && point.y >= self.y()
+            && point.y < self.bottom()
+    }
+
+    #[allow(dead_code)]
+    pub fn contains(&self, other: &Rect) -> bool {
+        other.x() >= self.x()
+            && other.right() <= self.right()
+            && other.y() >= self.y()
+            && other.bottom() <= self.bottom()
+    }
+
+    /// The smallest rect covering both rects.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x = self.x().min(other.x());
+        let y = self.y().min(other.y());
+        Rect::new_from_x_y(
+            x,
+            y,
+            self.right().max(other.right()) - x,
+            self.bottom().max(other.bottom()) - y,
+        )
+    }
+
+    /// Grows the rect by `dx` on the left and right and `dy` on the top and bottom.
+    #[allow(dead_code)]
+    pub fn inflate(&self, dx: i32, dy: i32) -> Rect {
+        Rect::new_from_x_y(
+            self.x() - dx,
+            self.y() - dy,
+            (self.width + dx * 2).max(0),
+            (self.height + dy * 2).max(0),
+        )
+    }
+
+    #[allow(dead_code)]
+    pub fn deflate(&self, dx: i32, dy: i32) -> Rect {
+        self.inflate(-dx, -dy)
+    }
+
+    /// Moves this rect by `velocity` and finds the first moment it would hit `other`.
+    /// For a moving `other`, pass the velocity relative to it. Rects that only
+    /// touch at the end of the move don't hit, matching `intersects`.
+    pub fn sweep(&self, velocity: Vector, other: &Rect) -> Option<Sweep> {
+        if self.intersects(other) {
+            return Some(Sweep {
+                time: 0.0,
+                normal: Point::default(),
+            });
+        }
+
+        // Sweeping a point against `other` grown by this rect's size is the same test
+        let (entry_x, exit_x) = slab(
+            self.x() as f32,
+            velocity.x,
+            (other.x() - self.width) as f32,
+            other.right() as f32,
+        )?;
+        let (entry_y, exit_y) = slab(
+            self.y() as f32,
+            velocity.y,
+            (other.y() - self.height) as f32,
+            other.bottom() as f32,
+        )?;
+        let entry = entry_x.max(entry_y);
+        let exit = exit_x.min(exit_y);
+        if entry >= exit || !(0.0..1.0).contains(&entry) {
+            return None;
+        }
+
+        let normal = if entry_x > entry_y {
+            Point {
+                x: -velocity.x.signum() as i32,
+                y: 0,
+            }
+        } else {
+            Point {
+                x: 0,
+                y: -velocity.y.signum() as i32,
+            }
+        };
+        Some(Sweep {
+            time: entry,
+            normal,
+        })
+    }
+}
+
+/// Where a swept rect first hits another.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sweep {
+    /// Fraction of the move completed before the hit, from 0 to 1.
+    pub time: f32,
+    /// The side of the other rect that was hit, pointing back at the mover.
+    pub normal: Point,
+}
+
+/// Entry and exit times of a point moving along one axis through the open
+/// interval `min..max`.
+fn slab(origin: f32, velocity: f32, min: f32, max: f32) -> Option<(f32, f32)> {
+    if velocity == 0.0 {
+        return (origin > min && origin < max).then_some((f32::NEG_INFINITY, f32::INFINITY));
+    }
+    let first = (min - origin) / velocity;
+    let second = (max - origin) / velocity;
+    Some((first.min(second), first.max(second)))
 }
 
 #[cfg(test)]
@@ -62,7 +208,7 @@ mod tests {
         let rect = Rect {
             position: Point { x: 10, y: 10 },
             width: 100,
-            height: 100
+            height: 100,
         };
 
         let other = Rect {
@@ -92,4 +238,150 @@ mod tests {
         assert!(!rect.intersects(&other));
         assert!(!other.intersects(&rect));
     }
+
+    #[test]
+    fn intersection_is_the_overlapping_area() {
+        let rect = Rect::new_from_x_y(0, 0, 10, 10);
+        let other = Rect::new_from_x_y(6, 4, 10, 10);
+
+        assert_eq!(
+            rect.intersection(&other),
+            Some(Rect::new_from_x_y(6, 4, 4, 6))
+        );
+        assert_eq!(rect.intersection(&other), other.intersection(&rect));
+    }
+
+    #[test]
+    fn touching_rects_have_no_intersection() {
+        let rect = Rect::new_from_x_y(0, 0, 10, 10);
+        let other = Rect::new_from_x_y(10, 0, 10, 10);
+
+        assert_eq!(rect.intersection(&other), None);
+        assert_eq!(rect.minimum_translation(&other), None);
+    }
+
+    #[test]
+    fn minimum_translation_pushes_out_along_the_shallowest_axis() {
+        let platform = Rect::new_from_x_y(100, 100, 200, 40);
+
+        let landing = Rect::new_from_x_y(150, 60, 20, 44);
+        assert_eq!(
+            landing.minimum_translation(&platform),
+            Some(Point { x: 0, y: -4 })
+        );
+
+        let from_the_left = Rect::new_from_x_y(83, 110, 20, 20);
+        assert_eq!(
+            from_the_left.minimum_translation(&platform),
+            Some(Point { x: -3, y: 0 })
+        );
+
+        let from_below = Rect::new_from_x_y(150, 135, 20, 20);
+        assert_eq!(
+            from_below.minimum_translation(&platform),
+            Some(Point { x: 0, y: 5 })
+        );
+    }
+
+    #[test]
+    fn applying_the_minimum_translation_separates_the_rects() {
+        let rect = Rect::new_from_x_y(95, 97, 20, 20);
+        let other = Rect::new_from_x_y(100, 100, 50, 50);
+
+        let translation = rect.minimum_translation(&other).unwrap();
+        let moved = Rect::new(rect.position + translation, rect.width, rect.height);
+
+        assert!(!moved.intersects(&other));
+    }
+
+    #[test]
+    fn contains_points_on_the_top_left_edges_only() {
+        let rect = Rect::new_from_x_y(0, 0, 10, 10);
+
+        assert!(rect.contains_point(&Point { x: 0, y: 0 }));
+        assert!(rect.contains_point(&Point { x: 9, y: 9 }));
+        assert!(!rect.contains_point(&Point { x: 10, y: 5 }));
+        assert!(!rect.contains_point(&Point { x: 5, y: -1 }));
+    }
+
+    #[test]
+    fn contains_rects_that_fit_inside() {
+        let rect = Rect::new_from_x_y(0, 0, 10, 10);
+
+        assert!(rect.contains(&rect));
+        assert!(rect.contains(&Rect::new_from_x_y(2, 2, 8, 8)));
+        assert!(!rect.contains(&Rect::new_from_x_y(2, 2, 9, 8)));
+        assert!(!Rect::new_from_x_y(2, 2, 8, 8).contains(&rect));
+    }
+
+    #[test]
+    fn union_covers_both_rects() {
+        let rect = Rect::new_from_x_y(0, 0, 10, 10);
+        let other = Rect::new_from_x_y(20, -5, 5, 5);
+
+        let union = rect.union(&other);
+
+        assert_eq!(union, Rect::new_from_x_y(0, -5, 25, 15));
+        assert!(union.contains(&rect) && union.contains(&other));
+    }
+
+    #[test]
+    fn inflate_and_deflate_keep_the_centre() {
+        let rect = Rect::new_from_x_y(10, 10, 20, 10);
+
+        assert_eq!(rect.inflate(2, 3), Rect::new_from_x_y(8, 7, 24, 16));
+        assert_eq!(rect.deflate(2, 3), Rect::new_from_x_y(12, 13, 16, 4));
+        assert_eq!(rect.deflate(20, 20).width, 0);
+    }
+
+    #[test]
+    fn sweeping_finds_the_time_and_side_of_impact() {
+        let rect = Rect::new_from_x_y(0, 0, 10, 10);
+        let wall = Rect::new_from_x_y(20, -5, 10, 30);
+
+        let hit = rect.sweep(Vector::new(20.0, 0.0), &wall).unwrap();
+
+        assert_eq!(hit.time, 0.5);
+        assert_eq!(hit.normal, Point { x: -1, y: 0 });
+    }
+
+    #[test]
+    fn sweeping_catches_fast_moves_through_thin_rects() {
+        let rect = Rect::new_from_x_y(0, 0, 10, 10);
+        let floor = Rect::new_from_x_y(-50, 30, 100, 2);
+
+        let hit = rect.sweep(Vector::new(5.0, 60.0), &floor).unwrap();
+
+        assert!(!Rect::new_from_x_y(5, 60, 10, 10).intersects(&floor));
+        assert_eq!(hit.time, 20.0 / 60.0);
+        assert_eq!(hit.normal, Point { x: 0, y: -1 });
+    }
+
+    #[test]
+    fn sweeping_misses() {
+        let rect = Rect::new_from_x_y(0, 0, 10, 10);
+        let other = Rect::new_from_x_y(20, 0, 10, 10);
+
+        assert_eq!(rect.sweep(Vector::new(-20.0, 0.0), &other), None);
+        assert_eq!(rect.sweep(Vector::new(5.0, 0.0), &other), None);
+        assert_eq!(rect.sweep(Vector::new(30.0, 40.0), &other), None);
+    }
+
+    #[test]
+    fn sweeping_into_contact_is_not_a_hit() {
+        let rect = Rect::new_from_x_y(0, 0, 10, 10);
+        let other = Rect::new_from_x_y(20, 0, 10, 10);
+
+        assert_eq!(rect.sweep(Vector::new(10.0, 0.0), &other), None);
+        assert_eq!(rect.sweep(Vector::new(0.0, 0.0), &other), None);
+    }
+
+    #[test]
+    fn sweeping_from_inside_hits_immediately() {
+        let rect = Rect::new_from_x_y(0, 0, 10, 10);
+
+        let hit = rect.sweep(Vector::new(3.0, 0.0), &rect).unwrap();
+
+        assert_eq!(hit.time, 0.0);
+    }
 }
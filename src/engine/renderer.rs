@@ -1,15 +1,57 @@
-use anyhow::{anyhow, Result};
+use std::cell::Cell;
+
+use anyhow::Result;
+use wasm_bindgen::JsValue;
 use web_sys::{CanvasRenderingContext2d, HtmlImageElement};
 
-use super::{Point, Rect};
+use super::{camera::Camera, error::EngineError, Point, Rect};
+
+const DEBUG_COLOR: &str = "lime";
 
 pub struct Renderer {
     context: CanvasRenderingContext2d,
+    debug: Cell<bool>,
 }
 
 impl Renderer {
     pub fn new(context: CanvasRenderingContext2d) -> Self {
-        Renderer { context }
+        Renderer {
+            context,
+            debug: Cell::new(false),
+        }
+    }
+
+    /// Turns the outlines drawn by `draw_debug_rect` on or off.
+    pub fn set_debug(&self, enabled: bool) {
+        self.debug.set(enabled);
+    }
+
+    /// Draws in world coordinates by translating the canvas by the camera position.
+    pub fn draw_with_camera<F>(&self, camera: &Camera, draw: F) -> Result<()>
+    where
+        F: FnOnce(&Renderer) -> Result<()>,
+    {
+        let origin = camera.view();
+        self.context.save();
+        let result = self
+            .context
+            .translate(-f64::from(origin.x), -f64::from(origin.y))
+            .map_err(|err| EngineError::browser("translate the canvas", err).into())
+            .and_then(|_| draw(self));
+        self.context.restore();
+        result
+    }
+
+    /// Draws with every pixel made more transparent by `alpha`.
+    pub fn with_alpha<F>(&self, alpha: f32, draw: F) -> Result<()>
+    where
+        F: FnOnce(&Renderer) -> Result<()>,
+    {
+        self.context.save();
+        self.context.set_global_alpha(alpha.into());
+        let result = draw(self);
+        self.context.restore();
+        result
     }
 
     pub fn clear(&self, rect: &Rect) {
@@ -27,7 +69,8 @@ impl Renderer {
         frame: &Rect,
         destination: &Rect,
     ) -> Result<()> {
-        self.context
+        Ok(self
+            .context
             .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
                 image,
                 frame.x().into(),
@@ -39,31 +82,104 @@ impl Renderer {
                 destination.width.into(),
                 destination.height.into(),
             )
-            .map_err(|err| anyhow!("Could not draw image {:#?}", err))
+            .map_err(|err| EngineError::browser("draw image", err))?)
     }
 
     pub fn draw_entire_image(&self, image: &HtmlImageElement, position: &Point) -> Result<()> {
-        self.context
+        Ok(self
+            .context
             .draw_image_with_html_image_element(image, position.x.into(), position.y.into())
-            .map_err(|err| anyhow!("Could not draw entire image {:#?}", err))
+            .map_err(|err| EngineError::browser("draw entire image", err))?)
     }
 
-    #[allow(dead_code)]
-    pub fn draw_rect(&self, rect: &Rect) {
+    pub fn fill_rect(&self, rect: &Rect, color: &str, alpha: f32) {
+        self.context.save();
+        self.context.set_global_alpha(alpha.into());
+        self.context.set_fill_style(&JsValue::from_str(color));
+        self.context.fill_rect(
+            rect.x().into(),
+            rect.y().into(),
+            rect.width.into(),
+            rect.height.into(),
+        );
+        self.context.restore();
+    }
+
+    pub fn stroke_rect(&self, rect: &Rect, color: &str, line_width: f32) {
+        self.context.save();
+        self.context.set_stroke_style(&JsValue::from_str(color));
+        self.context.set_line_width(line_width.into());
         self.context.stroke_rect(
             rect.x().into(),
             rect.y().into(),
             rect.width.into(),
             rect.height.into(),
         );
+        self.context.restore();
+    }
+
+    /// Draws `text` centred inside `area`.
+    pub fn draw_centered_text(
+        &self,
+        text: &str,
+        area: &Rect,
+        font: &str,
+        color: &str,
+    ) -> Result<()> {
+        self.context.save();
+        self.context.set_font(font);
+        self.context.set_fill_style(&JsValue::from_str(color));
+        self.context.set_text_align("center");
+        self.context.set_text_baseline("middle");
+        let result = self
+            .context
+            .fill_text(
+                text,
+                f64::from(area.x()) + f64::from(area.width) / 2.0,
+                f64::from(area.y()) + f64::from(area.height) / 2.0,
+            )
+            .map_err(|err| EngineError::browser("fill text", err).into());
+        self.context.restore();
+        result
+    }
+
+    /// Outlines a collider, only while debugging is on.
+    pub fn draw_debug_rect(&self, rect: &Rect) {
+        if self.debug.get() {
+            self.stroke_rect(rect, DEBUG_COLOR, 1.0);
+        }
     }
 
-    #[allow(dead_code)]
     pub fn draw_text(&self, text: &str, position: &Point) -> Result<()> {
-        self.context.set_font("16pt serif");
+        self.draw_scaled_text(text, position, 1.0)
+    }
+
+    /// Draws `text` from the top left of `position`.
+    pub fn draw_styled_text(
+        &self,
+        text: &str,
+        position: &Point,
+        font: &str,
+        color: &str,
+    ) -> Result<()> {
+        self.context.save();
+        self.context.set_font(font);
+        self.context.set_fill_style(&JsValue::from_str(color));
+        self.context.set_text_baseline("top");
+        let result = self
+            .context
+            .fill_text(text, position.x.into(), position.y.into())
+            .map_err(|err| EngineError::browser("fill text", err).into());
+        self.context.restore();
+        result
+    }
+
+    /// Draws text grown from its baseline by `scale`, for popping effects.
+    pub fn draw_scaled_text(&self, text: &str, position: &Point, scale: f32) -> Result<()> {
+        self.context.set_font(&format!("{}pt serif", 16.0 * scale));
         self.context
             .fill_text(text, position.x.into(), position.y.into())
-            .map_err(|err| anyhow!("Error filling text {:#?}", err))?;
+            .map_err(|err| EngineError::browser("fill text", err))?;
         Ok(())
     }
 }
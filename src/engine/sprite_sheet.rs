@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap};
 
 use anyhow::Result;
 use serde::Deserialize;
@@ -6,19 +6,23 @@ use web_sys::HtmlImageElement;
 
 use crate::browser;
 
-use super::{Point, Rect, Renderer};
+use super::{error::EngineError, Point, Rect, Renderer};
 
+/// A texture atlas. The cells and image can be swapped for fresh copies while
+/// the game runs, so every holder of the sheet sees the new art.
 pub struct SpriteSheet {
-    sheet: Sheet,
-    image: HtmlImageElement,
+    sheet: RefCell<Sheet>,
+    image: RefCell<HtmlImageElement>,
+    json_resource: String,
+    image_resource: String,
 }
 
 #[derive(Clone, Deserialize)]
 struct SheetRect {
-    x: i16,
-    y: i16,
-    w: i16,
-    h: i16,
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
 }
 
 #[derive(Clone, Deserialize)]
@@ -34,12 +38,11 @@ impl Cell {
     }
 
     pub fn destination(&self, position: &Point) -> Rect {
-        Rect::new_from_x_y(
-            position.x + self.sprite_source_size.x,
-            position.y + self.sprite_source_size.y,
-            self.frame.w,
-            self.frame.h,
-        )
+        let offset = Point {
+            x: self.sprite_source_size.x,
+            y: self.sprite_source_size.y,
+        };
+        Rect::new(*position + offset, self.frame.w, self.frame.h)
     }
 }
 
@@ -50,17 +53,49 @@ struct Sheet {
 
 impl SpriteSheet {
     pub async fn new(json_resource: &str, image_resource: &str) -> Result<Self> {
-        let sheet = serde_wasm_bindgen::from_value(browser::fetch_json(json_resource).await?)
-            .unwrap_or_else(|_| panic!("Could not load JSON resource {}", json_resource));
+        let sheet = load_sheet(json_resource).await?;
         let image = super::load_image(image_resource).await?;
-        Ok(SpriteSheet { sheet, image })
+        Ok(SpriteSheet {
+            sheet: RefCell::new(sheet),
+            image: RefCell::new(image),
+            json_resource: json_resource.to_string(),
+            image_resource: image_resource.to_string(),
+        })
     }
 
-    pub fn cell(&self, name: &str) -> Option<&Cell> {
-        self.sheet.frames.get(name)
+    pub fn cell(&self, name: &str) -> Option<Cell> {
+        self.sheet.borrow().frames.get(name).cloned()
+    }
+
+    /// Every cell name, shortest first so numbered cells come out in order.
+    pub fn cell_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.sheet.borrow().frames.keys().cloned().collect();
+        names.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+        names
+    }
+
+    pub fn resources(&self) -> [&str; 2] {
+        [&self.json_resource, &self.image_resource]
+    }
+
+    /// Fetches the JSON and image again, bypassing the browser cache. Both are
+    /// swapped in together once both have loaded, so a failure keeps the old art.
+    pub async fn reload(&self) -> Result<()> {
+        let sheet = load_sheet(&browser::cache_busted(&self.json_resource)?).await?;
+        let image = super::load_image(&browser::cache_busted(&self.image_resource)?).await?;
+        *self.sheet.borrow_mut() = sheet;
+        *self.image.borrow_mut() = image;
+        Ok(())
     }
 
     pub fn draw(&self, renderer: &Renderer, source: &Rect, destination: &Rect) -> Result<()> {
-        renderer.draw_image(&self.image, source, destination)
+        renderer.draw_image(&self.image.borrow(), source, destination)
     }
 }
+
+async fn load_sheet(json_resource: &str) -> Result<Sheet> {
+    Ok(
+        serde_wasm_bindgen::from_value(browser::fetch_json(json_resource).await?)
+            .map_err(|err| EngineError::resource(json_resource, err))?,
+    )
+}
@@ -0,0 +1,313 @@
+use std::{collections::VecDeque, f32::consts::PI};
+
+use super::{Point, Vector};
+
+/// Standard easing curves mapping progress `0.0..=1.0` to an eased fraction.
+/// Every curve starts at 0 and ends at 1, though some overshoot in between.
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    SineInOut,
+    BackOut,
+    ElasticOut,
+    BounceOut,
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+            Easing::BackOut => {
+                const OVERSHOOT: f32 = 1.70158;
+                1.0 + (OVERSHOOT + 1.0) * (t - 1.0).powi(3) + OVERSHOOT * (t - 1.0).powi(2)
+            }
+            Easing::ElasticOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+                }
+            }
+            Easing::BounceOut => bounce_out(t),
+        }
+    }
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// Values a tween can animate, such as positions, alpha or scale.
+pub trait Lerp: Copy {
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for Point {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Point {
+            x: (self.x as f32).lerp(to.x as f32, t).round() as i32,
+            y: (self.y as f32).lerp(to.y as f32, t).round() as i32,
+        }
+    }
+}
+
+impl Lerp for Vector {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Vector::lerp(self, to, t)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TweenStatus {
+    Running,
+    /// Reported once, on the update that finishes the tween.
+    Completed,
+    Finished,
+}
+
+/// Animates a value from `from` to `to` over a number of fixed updates.
+#[derive(Clone, Debug)]
+pub struct Tween<T: Lerp> {
+    from: T,
+    to: T,
+    duration: u16,
+    elapsed: u16,
+    easing: Easing,
+}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, duration: u16, easing: Easing) -> Self {
+        Tween {
+            from,
+            to,
+            duration,
+            elapsed: 0,
+            easing,
+        }
+    }
+
+    pub fn update(&mut self) -> TweenStatus {
+        if self.is_finished() {
+            return TweenStatus::Finished;
+        }
+        self.elapsed += 1;
+        if self.is_finished() {
+            TweenStatus::Completed
+        } else {
+            TweenStatus::Running
+        }
+    }
+
+    pub fn value(&self) -> T {
+        let progress = if self.duration == 0 {
+            1.0
+        } else {
+            f32::from(self.elapsed) / f32::from(self.duration)
+        };
+        self.from.lerp(self.to, self.easing.apply(progress))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+/// Tweens played one after another. The value holds at the end of the last
+/// tween once the sequence completes.
+#[derive(Clone, Debug)]
+pub struct Sequence<T: Lerp> {
+    current: Tween<T>,
+    next: VecDeque<Tween<T>>,
+}
+
+impl<T: Lerp> Sequence<T> {
+    pub fn new(first: Tween<T>) -> Self {
+        Sequence {
+            current: first,
+            next: VecDeque::new(),
+        }
+    }
+
+    pub fn then(mut self, tween: Tween<T>) -> Self {
+        self.next.push_back(tween);
+        self
+    }
+
+    pub fn update(&mut self) -> TweenStatus {
+        match self.current.update() {
+            TweenStatus::Completed => match self.next.pop_front() {
+                Some(tween) => {
+                    self.current = tween;
+                    if self.current.is_finished() {
+                        self.update()
+                    } else {
+                        TweenStatus::Running
+                    }
+                }
+                None => TweenStatus::Completed,
+            },
+            status => status,
+        }
+    }
+
+    pub fn value(&self) -> T {
+        self.current.value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Easing; 10] = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::SineInOut,
+        Easing::BackOut,
+        Easing::ElasticOut,
+        Easing::BounceOut,
+    ];
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "expected {} to be close to {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn every_curve_starts_at_zero_and_ends_at_one() {
+        for easing in ALL {
+            assert_close(easing.apply(0.0), 0.0);
+            assert_close(easing.apply(1.0), 1.0);
+        }
+    }
+
+    #[test]
+    fn progress_outside_the_unit_range_is_clamped() {
+        for easing in ALL {
+            assert_close(easing.apply(-1.0), 0.0);
+            assert_close(easing.apply(2.0), 1.0);
+        }
+    }
+
+    #[test]
+    fn in_and_out_curves_mirror_each_other() {
+        for t in [0.1, 0.25, 0.5, 0.8] {
+            assert_close(
+                Easing::QuadIn.apply(t),
+                1.0 - Easing::QuadOut.apply(1.0 - t),
+            );
+            assert_close(
+                Easing::CubicIn.apply(t),
+                1.0 - Easing::CubicOut.apply(1.0 - t),
+            );
+        }
+    }
+
+    #[test]
+    fn symmetric_curves_are_halfway_at_the_midpoint() {
+        assert_close(Easing::Linear.apply(0.5), 0.5);
+        assert_close(Easing::QuadInOut.apply(0.5), 0.5);
+        assert_close(Easing::SineInOut.apply(0.5), 0.5);
+    }
+
+    #[test]
+    fn back_out_overshoots_the_target() {
+        assert!(Easing::BackOut.apply(0.7) > 1.0);
+    }
+
+    #[test]
+    fn bounce_out_never_leaves_the_unit_range() {
+        for step in 0..=100 {
+            let value = Easing::BounceOut.apply(step as f32 / 100.0);
+            assert!((0.0..=1.0 + 1e-4).contains(&value));
+        }
+    }
+
+    #[test]
+    fn tweens_report_completion_once() {
+        let mut tween = Tween::new(0.0, 10.0, 2, Easing::Linear);
+
+        assert_eq!(tween.update(), TweenStatus::Running);
+        assert_close(tween.value(), 5.0);
+        assert_eq!(tween.update(), TweenStatus::Completed);
+        assert_eq!(tween.update(), TweenStatus::Finished);
+        assert_close(tween.value(), 10.0);
+    }
+
+    #[test]
+    fn points_are_tweened_per_axis() {
+        let mut tween = Tween::new(
+            Point { x: 0, y: 10 },
+            Point { x: 10, y: 0 },
+            2,
+            Easing::Linear,
+        );
+
+        tween.update();
+
+        assert_eq!(tween.value(), Point { x: 5, y: 5 });
+    }
+
+    #[test]
+    fn sequences_play_their_tweens_in_order() {
+        let mut sequence = Sequence::new(Tween::new(1.0, 2.0, 1, Easing::Linear)).then(Tween::new(
+            2.0,
+            1.0,
+            2,
+            Easing::Linear,
+        ));
+
+        assert_eq!(sequence.update(), TweenStatus::Running);
+        assert_close(sequence.value(), 2.0);
+        assert_eq!(sequence.update(), TweenStatus::Running);
+        assert_close(sequence.value(), 1.5);
+        assert_eq!(sequence.update(), TweenStatus::Completed);
+        assert_close(sequence.value(), 1.0);
+    }
+}
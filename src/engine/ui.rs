@@ -0,0 +1,392 @@
+use anyhow::Result;
+use web_sys::HtmlImageElement;
+
+use super::{
+    key_state::{PointerState, GAMEPAD_A, GAMEPAD_DOWN, GAMEPAD_LEFT, GAMEPAD_RIGHT, GAMEPAD_UP},
+    KeyState, Rect, Renderer,
+};
+
+const FONT: &str = "14pt 'Ken Future', sans-serif";
+const TEXT_COLOR: &str = "white";
+const PANEL_COLOR: &str = "#1d2b53";
+const PANEL_ALPHA: f32 = 0.85;
+const SLIDER_TRACK_COLOR: &str = "#44506e";
+const SLIDER_STEP: f32 = 0.1;
+
+const PREVIOUS_KEYS: [&str; 2] = ["ArrowUp", GAMEPAD_UP];
+const NEXT_KEYS: [&str; 3] = ["ArrowDown", "Tab", GAMEPAD_DOWN];
+const ACTIVATE_KEYS: [&str; 3] = ["Enter", "Space", GAMEPAD_A];
+const DECREASE_KEYS: [&str; 2] = ["ArrowLeft", GAMEPAD_LEFT];
+const INCREASE_KEYS: [&str; 2] = ["ArrowRight", GAMEPAD_RIGHT];
+
+/// Frames of the button sheet used by the page's CSS buttons, so canvas menus match them.
+const BUTTON_FRAMES: [Rect; 3] = [
+    Rect::new_from_x_y(72, 60, 82, 33),
+    Rect::new_from_x_y(158, 60, 82, 33),
+    Rect::new_from_x_y(244, 60, 82, 33),
+];
+
+/// Colours for the parts of widgets that signal state.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UiColors {
+    pub button: &'static str,
+    pub button_hot: &'static str,
+    pub button_active: &'static str,
+    pub focus: &'static str,
+    /// Whether buttons may use the button sheet, whose art is in the standard colours.
+    pub button_sheet: bool,
+}
+
+impl UiColors {
+    pub const STANDARD: UiColors = UiColors {
+        button: "#c0392b",
+        button_hot: "#e74c3c",
+        button_active: "#962d22",
+        focus: "#ffd700",
+        button_sheet: true,
+    };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum WidgetState {
+    Normal,
+    Hot,
+    Active,
+}
+
+enum Widget {
+    Panel(Rect),
+    Label {
+        text: String,
+        area: Rect,
+    },
+    Button {
+        text: String,
+        area: Rect,
+        state: WidgetState,
+        focused: bool,
+    },
+    Slider {
+        text: String,
+        area: Rect,
+        value: f32,
+        focused: bool,
+    },
+}
+
+#[derive(Default)]
+struct Navigation {
+    previous: bool,
+    next: bool,
+    activate: bool,
+    decrease: bool,
+    increase: bool,
+}
+
+impl Navigation {
+    fn read(keystate: &KeyState) -> Self {
+        let any = |keys: &[&str]| keys.iter().any(|key| keystate.is_just_pressed(key));
+        Navigation {
+            previous: any(&PREVIOUS_KEYS),
+            next: any(&NEXT_KEYS),
+            activate: any(&ACTIVATE_KEYS),
+            decrease: any(&DECREASE_KEYS),
+            increase: any(&INCREASE_KEYS),
+        }
+    }
+}
+
+/// Immediate-mode widgets drawn on the canvas. Call `begin` at the start of
+/// each update, then declare the widgets: each call reports its interaction
+/// straight away and the widgets are drawn by the next `draw`.
+///
+/// Keyboard and gamepad focus is only on while `set_keyboard_navigation` is
+/// enabled, so menus don't steal the game's controls.
+pub struct Ui {
+    widgets: Vec<Widget>,
+    pointer: PointerState,
+    navigation: Navigation,
+    keyboard_navigation: bool,
+    focus: Option<usize>,
+    focusable: usize,
+    button_image: Option<HtmlImageElement>,
+    colors: UiColors,
+}
+
+impl Ui {
+    pub fn new() -> Self {
+        Ui {
+            widgets: vec![],
+            pointer: PointerState::default(),
+            navigation: Navigation::default(),
+            keyboard_navigation: false,
+            focus: None,
+            focusable: 0,
+            button_image: None,
+            colors: UiColors::STANDARD,
+        }
+    }
+
+    /// Draws buttons with the page's button sheet instead of flat colours,
+    /// unless the colours in use don't match its art.
+    pub fn set_button_image(&mut self, image: HtmlImageElement) {
+        self.button_image = Some(image);
+    }
+
+    pub fn set_colors(&mut self, colors: UiColors) {
+        self.colors = colors;
+    }
+
+    pub fn set_keyboard_navigation(&mut self, enabled: bool) {
+        self.keyboard_navigation = enabled;
+        if !enabled {
+            self.focus = None;
+        }
+    }
+
+    pub fn begin(&mut self, keystate: &KeyState) {
+        self.pointer = *keystate.pointer();
+        self.navigation = if self.keyboard_navigation {
+            Navigation::read(keystate)
+        } else {
+            Navigation::default()
+        };
+
+        // Focus moves over the widgets declared during the previous update
+        let count = self.focusable;
+        if count > 0 {
+            if self.navigation.next {
+                self.focus = Some(self.focus.map_or(0, |focus| (focus + 1) % count));
+            } else if self.navigation.previous {
+                self.focus = Some(
+                    self.focus
+                        .map_or(count - 1, |focus| (focus + count - 1) % count),
+                );
+            } else if self.keyboard_navigation && self.focus.is_none() {
+                self.focus = Some(0);
+            }
+        }
+        self.focus = self.focus.filter(|focus| *focus < count);
+        self.widgets.clear();
+        self.focusable = 0;
+    }
+
+    pub fn panel(&mut self, area: Rect) {
+        self.widgets.push(Widget::Panel(area));
+    }
+
+    pub fn label(&mut self, text: &str, area: Rect) {
+        self.widgets.push(Widget::Label {
+            text: text.into(),
+            area,
+        });
+    }
+
+    /// Returns true on the update the button is clicked or activated.
+    pub fn button(&mut self, text: &str, area: Rect) -> bool {
+        let focused = self.next_focusable();
+        let (hovered, down, clicked) = self.pointer_over(&area);
+        if clicked && self.keyboard_navigation {
+            self.focus = Some(self.focusable - 1);
+        }
+        let state = if hovered && down {
+            WidgetState::Active
+        } else if hovered || focused {
+            WidgetState::Hot
+        } else {
+            WidgetState::Normal
+        };
+        self.widgets.push(Widget::Button {
+            text: text.into(),
+            area,
+            state,
+            focused,
+        });
+        clicked || (focused && self.navigation.activate)
+    }
+
+    /// A slider over `0.0..=1.0`. Returns true on updates that change `value`.
+    pub fn slider(&mut self, text: &str, area: Rect, value: &mut f32) -> bool {
+        let focused = self.next_focusable();
+        let (hovered, down, clicked) = self.pointer_over(&area);
+        if clicked && self.keyboard_navigation {
+            self.focus = Some(self.focusable - 1);
+        }
+        let mut new_value = *value;
+        if hovered && down {
+            new_value = (self.pointer.position.x - area.x()) as f32 / area.width.max(1) as f32;
+        }
+        if focused && self.navigation.decrease {
+            new_value -= SLIDER_STEP;
+        }
+        if focused && self.navigation.increase {
+            new_value += SLIDER_STEP;
+        }
+        let new_value = new_value.clamp(0.0, 1.0);
+        let changed = new_value != *value;
+        *value = new_value;
+        self.widgets.push(Widget::Slider {
+            text: text.into(),
+            area,
+            value: new_value,
+            focused,
+        });
+        changed
+    }
+
+    pub fn draw(&self, renderer: &Renderer) -> Result<()> {
+        self.widgets
+            .iter()
+            .try_for_each(|widget| self.draw_widget(renderer, widget))
+    }
+
+    fn draw_widget(&self, renderer: &Renderer, widget: &Widget) -> Result<()> {
+        match widget {
+            Widget::Panel(area) => {
+                renderer.fill_rect(area, PANEL_COLOR, PANEL_ALPHA);
+                Ok(())
+            }
+            Widget::Label { text, area } => {
+                renderer.draw_centered_text(text, area, FONT, TEXT_COLOR)
+            }
+            Widget::Button {
+                text,
+                area,
+                state,
+                focused,
+            } => {
+                self.draw_button_background(renderer, area, *state)?;
+                if *focused {
+                    renderer.stroke_rect(area, self.colors.focus, 3.0);
+                }
+                renderer.draw_centered_text(text, area, FONT, TEXT_COLOR)
+            }
+            Widget::Slider {
+                text,
+                area,
+                value,
+                focused,
+            } => {
+                renderer.fill_rect(area, SLIDER_TRACK_COLOR, 1.0);
+                let filled = (area.width as f32 * value).round() as i32;
+                renderer.fill_rect(
+                    &Rect::new(area.position, filled, area.height),
+                    self.colors.button,
+                    1.0,
+                );
+                if *focused {
+                    renderer.stroke_rect(area, self.colors.focus, 3.0);
+                }
+                renderer.draw_centered_text(text, area, FONT, TEXT_COLOR)
+            }
+        }
+    }
+
+    fn draw_button_background(
+        &self,
+        renderer: &Renderer,
+        area: &Rect,
+        state: WidgetState,
+    ) -> Result<()> {
+        let (frame, color) = match state {
+            WidgetState::Normal => (&BUTTON_FRAMES[0], self.colors.button),
+            WidgetState::Hot => (&BUTTON_FRAMES[1], self.colors.button_hot),
+            WidgetState::Active => (&BUTTON_FRAMES[2], self.colors.button_active),
+        };
+        match &self.button_image {
+            Some(image) if self.colors.button_sheet => renderer.draw_image(image, frame, area),
+            _ => {
+                renderer.fill_rect(area, color, 1.0);
+                Ok(())
+            }
+        }
+    }
+
+    /// Registers a focusable widget and reports whether it has focus.
+    fn next_focusable(&mut self) -> bool {
+        let index = self.focusable;
+        self.focusable += 1;
+        self.focus == Some(index)
+    }
+
+    /// Whether the pointer is over `area`, held down, and was just pressed there.
+    fn pointer_over(&self, area: &Rect) -> (bool, bool, bool) {
+        if area.contains_point(&self.pointer.position) {
+            (true, self.pointer.down, self.pointer.just_pressed)
+        } else {
+            (false, false, false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Point;
+
+    const FIRST: Rect = Rect::new_from_x_y(0, 0, 100, 40);
+    const SECOND: Rect = Rect::new_from_x_y(0, 50, 100, 40);
+
+    fn press(key: &str) -> KeyState {
+        let mut keystate = KeyState::new();
+        keystate.set_pressed(key);
+        keystate
+    }
+
+    fn menu(ui: &mut Ui, keystate: &KeyState) -> (bool, bool) {
+        ui.begin(keystate);
+        (ui.button("First", FIRST), ui.button("Second", SECOND))
+    }
+
+    #[test]
+    fn clicking_a_button_activates_it() {
+        let mut ui = Ui::new();
+        let mut keystate = KeyState::new();
+        keystate.set_pointer_down(Point { x: 10, y: 60 });
+
+        assert_eq!(menu(&mut ui, &keystate), (false, true));
+    }
+
+    #[test]
+    fn keyboard_focus_moves_between_buttons_and_wraps() {
+        let mut ui = Ui::new();
+        ui.set_keyboard_navigation(true);
+        menu(&mut ui, &KeyState::new());
+
+        assert_eq!(menu(&mut ui, &press("Enter")), (true, false));
+        menu(&mut ui, &press("ArrowDown"));
+        assert_eq!(menu(&mut ui, &press("Enter")), (false, true));
+        menu(&mut ui, &press(GAMEPAD_DOWN));
+        assert_eq!(menu(&mut ui, &press(GAMEPAD_A)), (true, false));
+    }
+
+    #[test]
+    fn keys_are_ignored_without_keyboard_navigation() {
+        let mut ui = Ui::new();
+        menu(&mut ui, &KeyState::new());
+
+        assert_eq!(menu(&mut ui, &press("Enter")), (false, false));
+    }
+
+    #[test]
+    fn sliders_follow_the_pointer_and_keys() {
+        let mut ui = Ui::new();
+        ui.set_keyboard_navigation(true);
+        let mut value = 0.5;
+        let mut keystate = KeyState::new();
+        keystate.set_pointer_down(Point { x: 25, y: 10 });
+
+        ui.begin(&keystate);
+        assert!(ui.slider("Volume", FIRST, &mut value));
+        assert_eq!(value, 0.25);
+
+        ui.begin(&press("ArrowRight"));
+        ui.slider("Volume", FIRST, &mut value);
+        assert!((value - 0.35).abs() < f32::EPSILON);
+
+        ui.begin(&press("ArrowLeft"));
+        value = 0.05;
+        ui.slider("Volume", FIRST, &mut value);
+        assert_eq!(value, 0.0);
+    }
+}
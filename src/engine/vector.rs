@@ -0,0 +1,179 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use super::{Point, Rect};
+
+/// A sub-pixel position or velocity for physics. Rounded to a `Point` only
+/// when something is drawn or collided.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vector {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Vector {
+    pub const ZERO: Vector = Vector::new(0.0, 0.0);
+
+    pub const fn new(x: f32, y: f32) -> Self {
+        Vector { x, y }
+    }
+
+    pub fn to_point(self) -> Point {
+        Point {
+            x: self.x.round() as i32,
+            y: self.y.round() as i32,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn dot(self, other: Vector) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    #[allow(dead_code)]
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    /// The unit vector pointing the same way, or zero for a zero vector.
+    #[allow(dead_code)]
+    pub fn normalize(self) -> Vector {
+        let length = self.length();
+        if length == 0.0 {
+            Vector::ZERO
+        } else {
+            self * (1.0 / length)
+        }
+    }
+
+    pub fn lerp(self, to: Vector, t: f32) -> Vector {
+        self + (to - self) * t
+    }
+
+    /// Clamps each axis separately between `min` and `max`.
+    #[allow(dead_code)]
+    pub fn clamp(self, min: Vector, max: Vector) -> Vector {
+        Vector::new(self.x.clamp(min.x, max.x), self.y.clamp(min.y, max.y))
+    }
+}
+
+impl Add for Vector {
+    type Output = Vector;
+
+    fn add(self, other: Vector) -> Vector {
+        Vector::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for Vector {
+    fn add_assign(&mut self, other: Vector) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Vector {
+    type Output = Vector;
+
+    fn sub(self, other: Vector) -> Vector {
+        Vector::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl SubAssign for Vector {
+    fn sub_assign(&mut self, other: Vector) {
+        *self = *self - other;
+    }
+}
+
+impl Mul<f32> for Vector {
+    type Output = Vector;
+
+    fn mul(self, scale: f32) -> Vector {
+        Vector::new(self.x * scale, self.y * scale)
+    }
+}
+
+impl Neg for Vector {
+    type Output = Vector;
+
+    fn neg(self) -> Vector {
+        Vector::new(-self.x, -self.y)
+    }
+}
+
+impl From<Point> for Vector {
+    fn from(point: Point) -> Self {
+        Vector::new(point.x as f32, point.y as f32)
+    }
+}
+
+impl From<&Rect> for Vector {
+    /// The top left corner of the rect.
+    fn from(rect: &Rect) -> Self {
+        rect.position.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_to_the_nearest_pixel() {
+        assert_eq!(Vector::new(1.4, -2.6).to_point(), Point { x: 1, y: -3 });
+    }
+
+    #[test]
+    fn arithmetic_works_per_axis() {
+        let a = Vector::new(1.0, 2.0);
+        let b = Vector::new(3.0, -1.0);
+
+        assert_eq!(a + b, Vector::new(4.0, 1.0));
+        assert_eq!(a - b, Vector::new(-2.0, 3.0));
+        assert_eq!(a * 2.0, Vector::new(2.0, 4.0));
+        assert_eq!(-a, Vector::new(-1.0, -2.0));
+
+        let mut c = a;
+        c += b;
+        c -= a;
+        assert_eq!(c, b);
+    }
+
+    #[test]
+    fn length_and_dot_product() {
+        assert_eq!(Vector::new(3.0, 4.0).length(), 5.0);
+        assert_eq!(Vector::new(1.0, 2.0).dot(Vector::new(3.0, 4.0)), 11.0);
+        assert_eq!(Vector::new(1.0, 0.0).dot(Vector::new(0.0, 1.0)), 0.0);
+    }
+
+    #[test]
+    fn normalizing_keeps_the_direction() {
+        assert_eq!(Vector::new(0.0, -8.0).normalize(), Vector::new(0.0, -1.0));
+        assert!((Vector::new(3.0, 4.0).normalize().length() - 1.0).abs() < f32::EPSILON);
+        assert_eq!(Vector::ZERO.normalize(), Vector::ZERO);
+    }
+
+    #[test]
+    fn lerp_moves_part_of_the_way() {
+        let from = Vector::new(0.0, 10.0);
+        let to = Vector::new(10.0, 0.0);
+
+        assert_eq!(from.lerp(to, 0.0), from);
+        assert_eq!(from.lerp(to, 0.5), Vector::new(5.0, 5.0));
+        assert_eq!(from.lerp(to, 1.0), to);
+    }
+
+    #[test]
+    fn clamping_works_per_axis() {
+        let clamped = Vector::new(-5.0, 50.0).clamp(Vector::ZERO, Vector::new(10.0, 10.0));
+
+        assert_eq!(clamped, Vector::new(0.0, 10.0));
+    }
+
+    #[test]
+    fn converts_from_rect_positions() {
+        let rect = Rect::new_from_x_y(4, -2, 10, 10);
+
+        assert_eq!(Vector::from(&rect), Vector::new(4.0, -2.0));
+        assert_eq!(Vector::from(&rect).to_point(), rect.position);
+    }
+}
@@ -1,100 +1,324 @@
+mod audio_controls;
 mod barrier;
+mod collectible;
+mod console;
+mod editor;
+mod events;
+mod high_scores;
+mod hud;
+mod menu;
+mod music;
 mod obstacle;
+mod particle_effects;
 mod platform;
 mod redhatboy;
+mod settings;
+mod sound_effects;
 mod walk_the_dog;
 
 use std::rc::Rc;
 
 use anyhow::Result;
 pub use barrier::Barrier;
+pub use collectible::Collectible;
+use events::{GameEvent, GameEvents};
+use hud::Hud;
+use music::Music;
 pub use obstacle::Obstacle;
+use particle_effects::ParticleEffects;
 pub use platform::Platform;
-use rand::{prelude::*, Rng};
+use rand::{prelude::*, rngs::StdRng, Rng};
 pub use redhatboy::RedHatBoy;
+use settings::Difficulty;
+pub use settings::Settings;
+use sound_effects::SoundEffects;
 pub use walk_the_dog::WalkTheDog;
 use web_sys::HtmlImageElement;
 
 use crate::{
-    engine::{Image, Renderer, SpriteSheet},
-    segment::{platform_and_stone, stone_and_platform},
+    engine::{
+        self,
+        broad_phase::BroadPhase,
+        camera::Camera,
+        hot_reload::HotReload,
+        juice::Juice,
+        parallax::{Parallax, ParallaxLayer},
+        profiler::Profiler,
+        Point, Rect, Renderer, SpriteSheet,
+    },
+    segment::{SegmentData, SegmentTable},
 };
 
-const WIDTH: i16 = 600;
-const HEIGHT: i16 = 600;
-const OBSTACLE_BUFFER: i16 = 20;
+const WIDTH: i32 = 600;
+const HEIGHT: i32 = 600;
+const CAMERA_ANCHOR: Point = Point { x: 40, y: 0 };
+const CAMERA_LOOK_AHEAD: f32 = 20.0;
+const CAMERA_SMOOTHING: f32 = 0.1;
+const CAMERA_DEAD_ZONE: i32 = 60;
+const CAMERA_HIGHEST: i32 = -120;
+const KNOCK_OUT_TRAUMA: f32 = 0.6;
+const KNOCK_OUT_HIT_STOP_FRAMES: u8 = 6;
+const KNOCK_OUT_FLASH: f32 = 0.8;
+const COLLECTIBLE_BONUS: i32 = 10;
 
 pub struct Walk {
-    backgrounds: [Image; 2],
+    background: Parallax,
     boy: RedHatBoy,
+    broad_phase: BroadPhase,
+    camera: Camera,
+    difficulty: Difficulty,
+    events: GameEvents,
+    foreground: Parallax,
+    hud: Hud,
+    juice: Juice,
+    music: Music,
     obstacle_sheet: Rc<SpriteSheet>,
     obstacles: Vec<Box<dyn Obstacle>>,
+    particles: ParticleEffects,
+    profiler: Profiler,
+    rng: StdRng,
+    seed: u64,
+    segments: Rc<SegmentTable>,
+    sound_effects: SoundEffects,
     stone: HtmlImageElement,
-    timeline: i16,
+    testing: Option<SegmentData>,
+    timeline: f32,
 }
 
 impl Walk {
     fn draw(&self, renderer: &Renderer) -> Result<()> {
-        self.backgrounds
-            .iter()
-            .try_for_each(|background| -> Result<()> {
-                background.draw(renderer)
-            })?;
-        self.boy.draw(renderer)?;
-        self.obstacles
-            .iter()
-            .try_for_each(|obstacle| -> Result<()> { obstacle.draw(renderer) })
+        let _scope = self.profiler.scope("draw walk");
+        self.background.draw(renderer, &self.camera)?;
+        renderer.draw_with_camera(&self.camera, |renderer| {
+            self.boy.draw(renderer)?;
+            self.obstacles
+                .iter()
+                .try_for_each(|obstacle| -> Result<()> { obstacle.draw(renderer) })?;
+            self.particles.draw(renderer)
+        })?;
+        self.foreground.draw(renderer, &self.camera)?;
+        self.hud.draw(renderer)?;
+        self.juice
+            .draw(renderer, &Rect::new(Point { x: 0, y: 0 }, WIDTH, HEIGHT));
+        Ok(())
     }
 
-    fn knocked_out(&self) -> bool {
-        self.boy.knocked_out()
+    fn follow_boy(&mut self) {
+        self.camera.follow(self.boy.position(), self.boy.velocity());
+    }
+
+    fn process_events(&mut self) {
+        for event in self.events.drain() {
+            self.sound_effects.play(event);
+            self.particles.handle(event, &self.boy);
+            if event == GameEvent::Collect {
+                self.hud.add_bonus(COLLECTIBLE_BONUS);
+            }
+            if event == GameEvent::KnockOut {
+                self.juice.add_trauma(KNOCK_OUT_TRAUMA);
+                self.juice.hit_stop(KNOCK_OUT_HIT_STOP_FRAMES);
+                self.juice.flash(KNOCK_OUT_FLASH);
+            }
+        }
+    }
+
+    fn update_effects(&mut self) {
+        self.particles.update(&self.boy);
+        self.hud.update();
+        self.juice.update();
+        self.camera.set_shake(self.juice.shake());
+    }
+
+    fn apply_settings(&mut self, settings: &Settings) {
+        self.juice.set_settings(settings.juice());
+        self.difficulty = settings.difficulty;
+    }
+
+    fn debug_info(&self) -> Vec<String> {
+        let position = self.boy.position();
+        let velocity = self.boy.velocity();
+        vec![
+            format!("Boy: {}", self.boy.state_name()),
+            format!("Position: {:.1}, {:.1}", position.x, position.y),
+            format!("Velocity: {:.1}, {:.1}", velocity.x, velocity.y),
+            format!("Obstacles: {}", self.obstacles.len()),
+            format!("Seed: {}", self.seed),
+        ]
     }
 
-    fn velocity(&self) -> i16 {
-        -self.boy.walking_speed()
+    fn knocked_out(&self) -> bool {
+        self.boy.knocked_out()
     }
 
     fn generate_next_segment(&mut self) {
-        let mut rng = thread_rng();
-        let next_segment = rng.gen_range(0..2);
-
-        let mut next_obstacles = match next_segment {
-            0 => stone_and_platform(
-                self.stone.clone(),
-                self.obstacle_sheet.clone(),
-                self.timeline + OBSTACLE_BUFFER,
-            ),
-            1 => platform_and_stone(
-                self.stone.clone(),
-                self.obstacle_sheet.clone(),
-                self.timeline + OBSTACLE_BUFFER,
-            ),
-            _ => vec![],
+        let _scope = self.profiler.scope("segment");
+        // Test runs from the editor repeat the segment being edited
+        let segment = match &self.testing {
+            Some(segment) => segment.clone(),
+            None => self.segments.choose(&mut self.rng),
         };
+        self.add_segment(&segment);
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        (self.rng, self.seed) = new_rng(Some(seed));
+    }
+
+    /// Places `segment` after the last one.
+    fn add_segment(&mut self, segment: &SegmentData) {
+        let origin = self.camera.left();
+        let offset = (origin + self.difficulty.obstacle_gap()) as f32 + self.timeline;
 
-        self.timeline += rightmost(&next_obstacles);
+        let mut next_obstacles = segment.build(&self.stone, &self.obstacle_sheet, offset);
+
+        // The timeline counts down in screen space, so measure it from the camera
+        self.timeline += (rightmost(&next_obstacles) - origin) as f32;
         self.obstacles.append(&mut next_obstacles);
+        self.index_obstacles();
+    }
+
+    /// Drops the obstacles that have scrolled off the left of the screen.
+    fn remove_passed_obstacles(&mut self) {
+        let camera_left = self.camera.left();
+        let count = self.obstacles.len();
+        self.obstacles
+            .retain(|obstacle| obstacle.right() > camera_left);
+        if self.obstacles.len() != count {
+            self.index_obstacles();
+        }
+    }
+
+    fn clear_obstacles(&mut self) {
+        self.obstacles.clear();
+        self.index_obstacles();
+    }
+
+    /// Obstacles stay put in the world, so the broad phase only needs
+    /// rebuilding when one is added or removed.
+    fn index_obstacles(&mut self) {
+        self.broad_phase = index(&self.obstacles);
     }
 
     fn reset(walk: Self) -> Self {
-        let starting_obstacles =
-            stone_and_platform(walk.stone.clone(), walk.obstacle_sheet.clone(), 0);
-        let timeline = rightmost(&starting_obstacles);
+        let starting_obstacles = walk
+            .testing
+            .clone()
+            .unwrap_or_else(|| walk.segments.starting())
+            .build(&walk.stone, &walk.obstacle_sheet, 0.0);
+        let timeline = rightmost(&starting_obstacles) as f32;
+        // Every run gets its own seed, which the console shows and can replay
+        let (rng, seed) = new_rng(None);
+        let boy = RedHatBoy::reset(walk.boy);
+        let mut camera = walk.camera;
+        let mut particles = walk.particles;
+        particles.clear();
+        camera.snap_to(boy.position(), boy.velocity());
 
         Walk {
-            backgrounds: walk.backgrounds,
-            boy: RedHatBoy::reset(walk.boy),
+            background: walk.background,
+            boy,
+            broad_phase: index(&starting_obstacles),
+            camera,
+            difficulty: walk.difficulty,
+            events: walk.events,
+            foreground: walk.foreground,
+            hud: Hud::new(),
+            juice: walk.juice,
+            music: walk.music,
             obstacle_sheet: walk.obstacle_sheet,
             obstacles: starting_obstacles,
+            particles,
+            profiler: walk.profiler,
+            rng,
+            seed,
+            segments: walk.segments,
+            sound_effects: walk.sound_effects,
             stone: walk.stone,
+            testing: walk.testing,
             timeline,
         }
     }
 }
 
-const TIMELINE_MINIMUM: i16 = 1000;
+pub fn console_help() -> Vec<String> {
+    console::commands().help()
+}
+
+const TIMELINE_MINIMUM: f32 = 1000.0;
+
+/// Seeds the segment generator, returning the seed so a run can be repeated.
+fn new_rng(seed: Option<u64>) -> (StdRng, u64) {
+    let seed = seed.unwrap_or_else(|| thread_rng().gen());
+    log!("Choosing segments with seed {}", seed);
+    (StdRng::seed_from_u64(seed), seed)
+}
+
+fn new_camera(boy: &RedHatBoy) -> Camera {
+    let mut camera = Camera::new(CAMERA_ANCHOR, CAMERA_LOOK_AHEAD, CAMERA_SMOOTHING);
+    camera.follow_vertically(CAMERA_DEAD_ZONE, CAMERA_HIGHEST);
+    camera.snap_to(boy.position(), boy.velocity());
+    camera
+}
+
+async fn load_background(hot_reload: &mut HotReload) -> Result<Parallax> {
+    let sky = load_image("assets/original/freetileset/png/BG/BG.png", hot_reload).await?;
+    let mut background = Parallax::new(WIDTH);
+    // Starts above the canvas so following the boy up never uncovers its top edge
+    background.add_layer(ParallaxLayer::new(sky, 0.3, -40, 0));
+    if let Some(trees) = load_optional_image(
+        "assets/original/freetileset/png/Object/Tree_2.png",
+        hot_reload,
+    )
+    .await
+    {
+        let y = HEIGHT - trees.height() as i32;
+        background.add_layer(ParallaxLayer::new(trees, 0.6, y, 420));
+    }
+    Ok(background)
+}
+
+async fn load_foreground(hot_reload: &mut HotReload) -> Parallax {
+    const GRASS_OVERHANG: i32 = 20;
+
+    let mut foreground = Parallax::new(WIDTH);
+    if let Some(grass) = load_optional_image(
+        "assets/original/freetileset/png/Object/Bush (1).png",
+        hot_reload,
+    )
+    .await
+    {
+        let y = HEIGHT + GRASS_OVERHANG - grass.height() as i32;
+        foreground.add_layer(ParallaxLayer::new(grass, 1.4, y, 700));
+    }
+    foreground
+}
+
+/// Loads an image that development mode reloads when it changes.
+async fn load_image(resource: &str, hot_reload: &mut HotReload) -> Result<HtmlImageElement> {
+    let image = engine::load_image(resource).await?;
+    hot_reload.watch_image(resource, &image);
+    Ok(image)
+}
+
+/// Decorative images are optional; a missing one is only skipped.
+async fn load_optional_image(
+    resource: &str,
+    hot_reload: &mut HotReload,
+) -> Option<HtmlImageElement> {
+    match load_image(resource, hot_reload).await {
+        Ok(image) => Some(image),
+        Err(err) => {
+            warn!("Skipping image {}: {:#?}", resource, err);
+            None
+        }
+    }
+}
+
+fn index(obstacle_list: &[Box<dyn Obstacle>]) -> BroadPhase {
+    BroadPhase::new(obstacle_list.iter().map(|obstacle| obstacle.bounding_box()))
+}
 
-fn rightmost(obstacle_list: &[Box<dyn Obstacle>]) -> i16 {
+fn rightmost(obstacle_list: &[Box<dyn Obstacle>]) -> i32 {
     obstacle_list
         .iter()
         .map(|obstacle| obstacle.right())
@@ -0,0 +1,55 @@
+use super::events::{GameEvent, GameEvents};
+use crate::engine::{
+    audio::{Audio, AudioSettings, Channel, UnlockState},
+    ui::Ui,
+    KeyState, Rect,
+};
+
+pub const MUTE_KEY: &str = "KeyM";
+pub const VOLUME_DOWN_KEY: &str = "Minus";
+pub const VOLUME_UP_KEY: &str = "Equal";
+const VOLUME_STEP: f32 = 0.1;
+const UNLOCK_HINT: Rect = Rect::new_from_x_y(150, 16, 310, 30);
+const MUTE_BUTTON: Rect = Rect::new_from_x_y(480, 10, 110, 36);
+const VOLUME_SLIDER: Rect = Rect::new_from_x_y(480, 52, 110, 24);
+
+pub struct AudioControls {
+    audio: Audio,
+    events: GameEvents,
+}
+
+impl AudioControls {
+    pub fn new(audio: Audio, events: GameEvents) -> Self {
+        AudioControls { audio, events }
+    }
+
+    /// Edits `settings`, which the caller applies to the audio.
+    pub fn update(&mut self, keystate: &KeyState, ui: &mut Ui, settings: &mut AudioSettings) {
+        let label = if settings.muted {
+            "Sound off"
+        } else {
+            "Sound on"
+        };
+        let clicked = ui.button(label, MUTE_BUTTON);
+        if clicked {
+            self.events.publish(GameEvent::ButtonClick);
+        }
+        if keystate.is_just_pressed(MUTE_KEY) || clicked {
+            settings.muted = !settings.muted;
+        }
+        let mut volume = settings.volume(Channel::Master);
+        ui.slider("Volume", VOLUME_SLIDER, &mut volume);
+        if keystate.is_just_pressed(VOLUME_DOWN_KEY) {
+            volume -= VOLUME_STEP;
+        }
+        if keystate.is_just_pressed(VOLUME_UP_KEY) {
+            volume += VOLUME_STEP;
+        }
+        settings.set_volume(Channel::Master, volume);
+
+        if self.audio.unlock_state() == UnlockState::Locked {
+            ui.panel(UNLOCK_HINT);
+            ui.label("Click or press a key for sound", UNLOCK_HINT);
+        }
+    }
+}
@@ -1,22 +1,45 @@
 use anyhow::Result;
 
-use crate::engine::Image;
+use crate::engine::{Image, Rect};
 
 use super::obstacle::Obstacle;
 
 pub struct Barrier {
     image: Image,
+    bounding_box: Rect,
 }
 
 impl Barrier {
     pub fn new(image: Image) -> Self {
-        Barrier { image }
+        let bounding_box = *image.bounding_box();
+        Barrier {
+            image,
+            bounding_box,
+        }
+    }
+
+    /// A barrier that collides over `bounding_box`, relative to the image,
+    /// rather than the whole image.
+    pub fn with_bounding_box(image: Image, bounding_box: Rect) -> Self {
+        let bounding_box = Rect::new(
+            image.position().to_point() + bounding_box.position,
+            bounding_box.width,
+            bounding_box.height,
+        );
+        Barrier {
+            image,
+            bounding_box,
+        }
     }
 }
 
 impl Obstacle for Barrier {
+    fn bounding_box(&self) -> Rect {
+        self.bounding_box
+    }
+
     fn check_intersection(&self, boy: &mut super::redhatboy::RedHatBoy) {
-        if boy.bounding_box().intersects(self.image.bounding_box()) {
+        if boy.sweep(&self.bounding_box).is_some() {
             boy.knock_out();
         }
     }
@@ -25,11 +48,7 @@ impl Obstacle for Barrier {
         self.image.draw(renderer)
     }
 
-    fn move_horizontally(&mut self, dx: i16) {
-        self.image.move_horizontally(dx);
-    }
-
-    fn right(&self) -> i16 {
-        self.image.bounding_box().right()
+    fn right(&self) -> i32 {
+        self.bounding_box.right()
     }
 }
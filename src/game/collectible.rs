@@ -0,0 +1,70 @@
+use std::{cell::Cell, rc::Rc};
+
+use anyhow::Result;
+
+use crate::engine::{Rect, Renderer, SpriteSheet, Vector};
+
+use super::{Obstacle, RedHatBoy};
+
+/// A tile the boy picks up by running through it, worth bonus points.
+pub struct Collectible {
+    bounding_box: Rect,
+    position: Vector,
+    sheet: Rc<SpriteSheet>,
+    sprite_name: String,
+    // Obstacles are checked through shared references
+    collected: Cell<bool>,
+}
+
+impl Collectible {
+    pub fn new(
+        bounding_box: Rect,
+        position: Vector,
+        sheet: Rc<SpriteSheet>,
+        sprite_name: &str,
+    ) -> Self {
+        Collectible {
+            bounding_box: Rect::new(
+                position.to_point() + bounding_box.position,
+                bounding_box.width,
+                bounding_box.height,
+            ),
+            position,
+            sheet,
+            sprite_name: sprite_name.to_string(),
+            collected: Cell::new(false),
+        }
+    }
+}
+
+impl Obstacle for Collectible {
+    fn bounding_box(&self) -> Rect {
+        self.bounding_box
+    }
+
+    fn check_intersection(&self, boy: &mut RedHatBoy) {
+        if !self.collected.get() && boy.sweep(&self.bounding_box).is_some() {
+            self.collected.set(true);
+            boy.collect();
+        }
+    }
+
+    fn draw(&self, renderer: &Renderer) -> Result<()> {
+        if self.collected.get() {
+            return Ok(());
+        }
+        renderer.draw_debug_rect(&self.bounding_box);
+        match self.sheet.cell(&self.sprite_name) {
+            Some(sprite) => self.sheet.draw(
+                renderer,
+                &sprite.frame(),
+                &sprite.destination(&self.position.to_point()),
+            ),
+            None => Ok(()),
+        }
+    }
+
+    fn right(&self) -> i32 {
+        self.bounding_box.right()
+    }
+}
@@ -0,0 +1,261 @@
+use anyhow::{anyhow, Result};
+use futures::channel::mpsc::UnboundedReceiver;
+
+use super::{redhatboy::Physics, Settings, Walk, WIDTH};
+use crate::{
+    browser::{
+        self,
+        ui::{Output, Panel, TextInput},
+    },
+    engine::{
+        console::{self, argument, Command, Commands, Submitted, CONSOLE_KEY},
+        KeyState,
+    },
+};
+
+const HISTORY_LINES: usize = 100;
+
+/// The backtick developer console, which runs `commands` on the walk. Lines
+/// submitted from JavaScript run here too, with their output sent back and
+/// logged. Its cheats would spoil high scores, so only debug builds have it.
+pub struct DevConsole {
+    history: Vec<String>,
+    open: Option<OpenConsole>,
+}
+
+/// What commands act on: the walk, and the settings for those that are kept.
+pub struct Target<'a> {
+    walk: &'a mut Walk,
+    settings: &'a mut Settings,
+}
+
+struct OpenConsole {
+    _panel: Panel,
+    output: Output,
+    input: UnboundedReceiver<TextInput>,
+}
+
+impl DevConsole {
+    pub fn new() -> Self {
+        DevConsole {
+            history: vec!["Type help for a list of commands".to_string()],
+            open: None,
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        keystate: &KeyState,
+        walk: &mut Walk,
+        settings: &mut Settings,
+    ) -> Result<()> {
+        if !cfg!(debug_assertions) {
+            return Ok(());
+        }
+        let mut target = Target { walk, settings };
+        if keystate.is_just_pressed(CONSOLE_KEY) {
+            self.toggle()?;
+        }
+        for Submitted { line, reply } in console::take_submitted() {
+            let output = self.run(&mut target, &line);
+            match &output {
+                Ok(output) => log!("> {}\n{}", line, output),
+                Err(err) => warn!("> {}\n{}", line, err),
+            }
+            // JavaScript may not wait for the answer
+            let _ = reply.send(output);
+        }
+
+        let mut lines = vec![];
+        let mut cancelled = false;
+        if let Some(open) = &mut self.open {
+            while let Ok(Some(input)) = open.input.try_next() {
+                match input {
+                    TextInput::Submit(line) => lines.push(line),
+                    TextInput::Cancel => cancelled = true,
+                }
+            }
+        }
+        for line in lines {
+            // The panel shows the output with the rest of the history
+            let _ = self.run(&mut target, &line);
+        }
+        if cancelled {
+            self.open = None;
+        }
+        Ok(())
+    }
+
+    fn toggle(&mut self) -> Result<()> {
+        self.open = match self.open {
+            Some(_) => {
+                browser::focus_canvas()?;
+                None
+            }
+            None => {
+                let mut panel = Panel::new("console")?;
+                let output = panel.output()?;
+                output.set_lines(&self.history);
+                let input = panel.text_input("Command")?;
+                Some(OpenConsole {
+                    _panel: panel,
+                    output,
+                    input,
+                })
+            }
+        };
+        Ok(())
+    }
+
+    /// Runs `line`, recording it and what it printed in the history.
+    fn run(&mut self, target: &mut Target, line: &str) -> console::Output {
+        let output = commands()
+            .run(target, line)
+            .map_err(|err| format!("{:#}", err));
+        let (Ok(text) | Err(text)) = &output;
+        self.history.push(format!("> {}", line));
+        self.history.extend(text.lines().map(ToString::to_string));
+        let overflow = self.history.len().saturating_sub(HISTORY_LINES);
+        self.history.drain(..overflow);
+        if let Some(open) = &self.open {
+            open.output.set_lines(&self.history);
+        }
+        output
+    }
+}
+
+pub fn commands<'a>() -> Commands<Target<'a>> {
+    Commands::new(vec![
+        Command {
+            name: "set",
+            usage: "[<tunable> <value>]",
+            help: "sets gravity, terminal_velocity, jumping_speed or running_speed",
+            run: set,
+        },
+        Command {
+            name: "invincible",
+            usage: "[on|off]",
+            help: "stops obstacles knocking the boy out",
+            run: invincible,
+        },
+        Command {
+            name: "spawn",
+            usage: "<segment>",
+            help: "makes the named segment the next one",
+            run: spawn,
+        },
+        Command {
+            name: "seed",
+            usage: "[<seed>]",
+            help: "shows or changes this run's seed for choosing segments, set before running to replay a run",
+            run: seed,
+        },
+        Command {
+            name: "goto",
+            usage: "<score>",
+            help: "moves the boy forward to a score",
+            run: goto,
+        },
+        Command {
+            name: "log",
+            usage: "[<filter>]",
+            help: "shows or changes the saved log filter, such as warn,engine::audio=debug",
+            run: log_filter,
+        },
+    ])
+}
+
+fn set(target: &mut Target, arguments: &[&str]) -> Result<String> {
+    let walk = &mut *target.walk;
+    let mut physics = walk.boy.physics();
+    if let Some(name) = arguments.first() {
+        let value = argument(arguments, 1, "value")?;
+        let tunable = match name.to_ascii_lowercase().as_str() {
+            "gravity" => &mut physics.gravity,
+            "terminal_velocity" => &mut physics.terminal_velocity,
+            "jumping_speed" => &mut physics.jumping_speed,
+            "running_speed" => &mut physics.running_speed,
+            _ => return Err(anyhow!("Unknown tunable '{}'", name)),
+        };
+        *tunable = value;
+        walk.boy.set_physics(physics);
+    }
+    Ok(describe(&physics))
+}
+
+fn describe(physics: &Physics) -> String {
+    let Physics {
+        gravity,
+        terminal_velocity,
+        jumping_speed,
+        running_speed,
+    } = physics;
+    format!(
+        "gravity {}\nterminal_velocity {}\njumping_speed {}\nrunning_speed {}",
+        gravity, terminal_velocity, jumping_speed, running_speed
+    )
+}
+
+fn invincible(target: &mut Target, arguments: &[&str]) -> Result<String> {
+    let walk = &mut *target.walk;
+    let invincible = match arguments.first().copied() {
+        None => !walk.boy.is_invincible(),
+        Some("on") => true,
+        Some("off") => false,
+        Some(other) => return Err(anyhow!("Expected on or off, not '{}'", other)),
+    };
+    walk.boy.set_invincible(invincible);
+    Ok(format!(
+        "Invincible {}",
+        if invincible { "on" } else { "off" }
+    ))
+}
+
+fn spawn(target: &mut Target, arguments: &[&str]) -> Result<String> {
+    let walk = &mut *target.walk;
+    let name = arguments
+        .first()
+        .ok_or_else(|| anyhow!("Missing segment"))?;
+    let segment = walk.segments.find(name).ok_or_else(|| {
+        anyhow!(
+            "Unknown segment '{}', try {}",
+            name,
+            walk.segments.names().join(" or ")
+        )
+    })?;
+    walk.add_segment(&segment);
+    Ok(format!("Spawned {}", name))
+}
+
+fn seed(target: &mut Target, arguments: &[&str]) -> Result<String> {
+    let walk = &mut *target.walk;
+    if !arguments.is_empty() {
+        walk.reseed(argument(arguments, 0, "seed")?);
+    }
+    Ok(format!("Seed {}", walk.seed))
+}
+
+fn goto(target: &mut Target, arguments: &[&str]) -> Result<String> {
+    let walk = &mut *target.walk;
+    let score = argument(arguments, 0, "score")?;
+    if score < walk.hud.score() {
+        return Err(anyhow!("The boy can only move forward"));
+    }
+    let start = walk.hud.distance();
+    walk.hud.set_score(score);
+    walk.boy.move_forward(walk.hud.distance() - start);
+    walk.camera
+        .snap_to(walk.boy.position(), walk.boy.velocity());
+    // Start generating again just off the right of the screen
+    walk.clear_obstacles();
+    walk.timeline = WIDTH as f32;
+    Ok(format!("Moved to {}", score))
+}
+
+fn log_filter(target: &mut Target, arguments: &[&str]) -> Result<String> {
+    if !arguments.is_empty() {
+        // Filters have no spaces, so `warn, engine=debug` is taken as one
+        target.settings.log_filter = arguments.join("");
+    }
+    Ok(format!("Log filter '{}'", target.settings.log_filter))
+}
@@ -0,0 +1,564 @@
+use std::rc::Rc;
+
+use anyhow::Result;
+use web_sys::HtmlImageElement;
+
+use super::events::{GameEvent, GameEvents};
+use crate::{
+    browser,
+    engine::{
+        camera::Camera, parallax::Parallax, ui::Ui, KeyState, Point, Rect, Renderer, SpriteSheet,
+        Vector,
+    },
+    segment::{self, ObstacleData, SegmentData, FLOATING_PLATFORM_SPRITES},
+};
+
+const EDITOR_SEGMENT_KEY: &str = "walk_the_dog.editor_segment";
+const EXPORT_FILE_SUFFIX: &str = ".segment.json";
+
+/// Positions and sizes snap to this many pixels.
+const SNAP: i32 = 10;
+const GRID: i32 = 50;
+const HANDLE_SIZE: i32 = 10;
+const SCROLL_STEP: i32 = 50;
+const MIN_SCROLL: i32 = -200;
+const SCROLL_LEFT_KEY: &str = "ArrowLeft";
+const SCROLL_RIGHT_KEY: &str = "ArrowRight";
+const DELETE_KEYS: [&str; 2] = ["Delete", "Backspace"];
+
+// Below the audio controls, which stay in the top right corner
+const TOOLBAR_Y: i32 = 84;
+const TOOLBAR_BUTTON_WIDTH: i32 = 80;
+const TOOLBAR_BUTTON_HEIGHT: i32 = 30;
+const TOOLBAR_SPACING: i32 = 84;
+const HINT_POSITION: Point = Point { x: 10, y: 122 };
+const EDIT_AREA_TOP: i32 = 140;
+const PALETTE: Rect = Rect::new_from_x_y(0, 530, 600, 70);
+const PALETTE_TILE: i32 = 40;
+const CLEAR_BRUSH_BUTTON: Rect = Rect::new_from_x_y(500, 540, 90, 30);
+
+const HINT_FONT: &str = "12px monospace";
+const HINT_COLOR: &str = "white";
+const GRID_COLOR: &str = "white";
+const GRID_ALPHA: f32 = 0.15;
+const ORIGIN_COLOR: &str = "yellow";
+const BOX_COLOR: &str = "lime";
+const SELECTED_COLOR: &str = "yellow";
+const PALETTE_COLOR: &str = "#1d2b53";
+const PALETTE_ALPHA: f32 = 0.85;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Tool {
+    Select,
+    Barrier,
+    Platform,
+    Collectible,
+}
+
+impl Tool {
+    fn uses_tiles(self) -> bool {
+        matches!(self, Tool::Platform | Tool::Collectible)
+    }
+}
+
+pub enum EditorAction {
+    TestPlay(SegmentData),
+    Exit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Drag {
+    /// Moving the selected obstacle, held `grab` from its position.
+    Move { grab: Point },
+    /// Resizing the selected obstacle's bounding box from its bottom right corner.
+    Resize { index: usize },
+}
+
+/// The segment being edited and what the pointer is doing to it, in segment
+/// coordinates.
+#[derive(Debug)]
+struct Layout {
+    segment: SegmentData,
+    stone_box: Rect,
+    selected: Option<usize>,
+    drag: Option<Drag>,
+}
+
+impl Layout {
+    fn new(segment: SegmentData, stone_box: Rect) -> Self {
+        Layout {
+            segment,
+            stone_box,
+            selected: None,
+            drag: None,
+        }
+    }
+
+    /// The bounding boxes of the obstacle at `index`, in segment coordinates.
+    fn boxes(&self, index: usize) -> Vec<Rect> {
+        let obstacle = &self.segment.obstacles[index];
+        let position = obstacle.position();
+        obstacle
+            .bounding_boxes(self.stone_box)
+            .into_iter()
+            .map(|bounding_box| {
+                Rect::new(
+                    position + bounding_box.position,
+                    bounding_box.width,
+                    bounding_box.height,
+                )
+            })
+            .collect()
+    }
+
+    /// The topmost obstacle under `point`.
+    fn obstacle_at(&self, point: Point) -> Option<usize> {
+        (0..self.segment.obstacles.len()).rev().find(|index| {
+            self.boxes(*index)
+                .iter()
+                .any(|bounding_box| bounding_box.contains_point(&point))
+        })
+    }
+
+    /// The selected obstacle's bounding box whose resize handle is under `point`.
+    fn handle_at(&self, point: Point) -> Option<usize> {
+        let selected = self.selected?;
+        self.boxes(selected)
+            .iter()
+            .position(|bounding_box| handle(bounding_box).contains_point(&point))
+    }
+
+    fn press(&mut self, point: Point) {
+        if let Some(index) = self.handle_at(point) {
+            self.drag = Some(Drag::Resize { index });
+            return;
+        }
+        self.selected = self.obstacle_at(point);
+        self.drag = self.selected.map(|selected| Drag::Move {
+            grab: point - self.segment.obstacles[selected].position(),
+        });
+    }
+
+    fn drag_to(&mut self, point: Point) {
+        let (Some(selected), Some(drag)) = (self.selected, self.drag) else {
+            return;
+        };
+        match drag {
+            Drag::Move { grab } => {
+                self.segment.obstacles[selected].set_position(snap_point(point - grab))
+            }
+            Drag::Resize { index } => {
+                let obstacle = &mut self.segment.obstacles[selected];
+                let Some(bounding_box) =
+                    obstacle.bounding_boxes(self.stone_box).get(index).copied()
+                else {
+                    return;
+                };
+                let corner = point - obstacle.position() - bounding_box.position;
+                obstacle.set_bounding_box(
+                    index,
+                    Rect::new(
+                        bounding_box.position,
+                        snap(corner.x).max(SNAP),
+                        snap(corner.y).max(SNAP),
+                    ),
+                );
+            }
+        }
+    }
+
+    fn release(&mut self) {
+        self.drag = None;
+    }
+
+    fn place(&mut self, obstacle: ObstacleData) {
+        self.segment.obstacles.push(obstacle);
+        self.selected = Some(self.segment.obstacles.len() - 1);
+    }
+
+    fn delete_selected(&mut self) {
+        if let Some(selected) = self.selected.take() {
+            self.segment.obstacles.remove(selected);
+            self.drag = None;
+        }
+    }
+}
+
+/// Lays out segments on the canvas: place barriers, and platforms and
+/// collectibles built from the tiles sprite sheet, drag them around and resize their bounding boxes,
+/// then test play the segment or export it as JSON.
+pub struct Editor {
+    layout: Layout,
+    tool: Tool,
+    brush: Vec<String>,
+    collectible: String,
+    scroll: i32,
+    camera: Camera,
+    stone: HtmlImageElement,
+    sheet: Rc<SpriteSheet>,
+    events: GameEvents,
+}
+
+impl Editor {
+    pub fn new(
+        segment: SegmentData,
+        stone: HtmlImageElement,
+        sheet: Rc<SpriteSheet>,
+        events: GameEvents,
+    ) -> Self {
+        let stone_box = Rect::new_from_x_y(0, 0, stone.width() as i32, stone.height() as i32);
+        let collectible = sheet.cell_names().into_iter().next().unwrap_or_default();
+        let mut editor = Editor {
+            layout: Layout::new(segment, stone_box),
+            tool: Tool::Select,
+            brush: FLOATING_PLATFORM_SPRITES
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            collectible,
+            scroll: 0,
+            camera: Camera::new(Point { x: 0, y: 0 }, 0.0, 1.0),
+            stone,
+            sheet,
+            events,
+        };
+        editor.scroll_by(0);
+        editor
+    }
+
+    /// Opens the segment from the last editing session, or a copy of `default`.
+    pub fn load(
+        default: SegmentData,
+        stone: HtmlImageElement,
+        sheet: Rc<SpriteSheet>,
+        events: GameEvents,
+    ) -> Self {
+        let segment = browser::load_json(EDITOR_SEGMENT_KEY)
+            .unwrap_or_else(|err| {
+                error!("Could not load the edited segment {:#?}", err);
+                None
+            })
+            .unwrap_or_else(|| SegmentData {
+                name: "custom".to_string(),
+                ..default
+            });
+        Editor::new(segment, stone, sheet, events)
+    }
+
+    pub fn update(&mut self, keystate: &KeyState, ui: &mut Ui) -> Option<EditorAction> {
+        let action = self.toolbar(ui);
+        if action.is_some() {
+            self.save();
+            return action;
+        }
+        if self.tool == Tool::Platform && self.button(ui, "Clear", CLEAR_BRUSH_BUTTON) {
+            self.brush.clear();
+        }
+
+        if keystate.is_just_pressed(SCROLL_LEFT_KEY) {
+            self.scroll_by(-SCROLL_STEP);
+        }
+        if keystate.is_just_pressed(SCROLL_RIGHT_KEY) {
+            self.scroll_by(SCROLL_STEP);
+        }
+        if DELETE_KEYS.iter().any(|key| keystate.is_just_pressed(key)) {
+            self.layout.delete_selected();
+        }
+
+        let pointer = keystate.pointer();
+        if self.tool.uses_tiles() && PALETTE.contains_point(&pointer.position) {
+            if pointer.just_pressed {
+                match (self.tool, self.tile_at(pointer.position)) {
+                    (Tool::Platform, Some(tile)) => self.brush.push(tile),
+                    (Tool::Collectible, Some(tile)) => self.collectible = tile,
+                    _ => {}
+                }
+            }
+            return None;
+        }
+        let point = pointer.position
+            + Point {
+                x: self.scroll,
+                y: 0,
+            };
+        if pointer.just_pressed && pointer.position.y >= EDIT_AREA_TOP {
+            match self.tool {
+                Tool::Select => self.layout.press(point),
+                Tool::Barrier => self.layout.place(segment::stone(snap_point(point))),
+                Tool::Platform if !self.brush.is_empty() => self.layout.place(segment::platform(
+                    snap_point(point),
+                    &self.brush,
+                    &self.sheet,
+                )),
+                Tool::Platform => {}
+                Tool::Collectible => self.layout.place(segment::collectible(
+                    snap_point(point),
+                    &self.collectible,
+                    &self.sheet,
+                )),
+            }
+        } else if pointer.down {
+            self.layout.drag_to(point);
+        }
+        if pointer.just_released {
+            self.layout.release();
+        }
+        None
+    }
+
+    pub fn draw(&self, renderer: &Renderer, background: &Parallax) -> Result<()> {
+        background.draw(renderer, &self.camera)?;
+        renderer.draw_with_camera(&self.camera, |renderer| {
+            self.draw_grid(renderer);
+            self.layout
+                .segment
+                .build(&self.stone, &self.sheet, 0.0)
+                .iter()
+                .try_for_each(|obstacle| obstacle.draw(renderer))?;
+            self.draw_boxes(renderer);
+            Ok(())
+        })?;
+        renderer.draw_styled_text(
+            "Arrows scroll, Delete removes, drag a corner to resize",
+            &HINT_POSITION,
+            HINT_FONT,
+            HINT_COLOR,
+        )?;
+        if self.tool.uses_tiles() {
+            self.draw_palette(renderer)?;
+        }
+        Ok(())
+    }
+
+    fn toolbar(&mut self, ui: &mut Ui) -> Option<EditorAction> {
+        let tools = [
+            ("Select", Tool::Select),
+            ("Barrier", Tool::Barrier),
+            ("Platform", Tool::Platform),
+            ("Item", Tool::Collectible),
+        ];
+        for (index, (label, tool)) in tools.into_iter().enumerate() {
+            let label = if self.tool == tool {
+                format!("[{}]", label)
+            } else {
+                label.to_string()
+            };
+            if self.button(ui, &label, toolbar_button(index)) {
+                self.tool = tool;
+            }
+        }
+        if self.button(ui, "Test", toolbar_button(4)) {
+            return Some(EditorAction::TestPlay(self.layout.segment.clone()));
+        }
+        if self.button(ui, "Export", toolbar_button(5)) {
+            if let Err(err) = self.export() {
+                error!("Could not export the segment {:#?}", err);
+            }
+        }
+        if self.button(ui, "Back", toolbar_button(6)) {
+            return Some(EditorAction::Exit);
+        }
+        None
+    }
+
+    fn button(&self, ui: &mut Ui, text: &str, area: Rect) -> bool {
+        let clicked = ui.button(text, area);
+        if clicked {
+            self.events.publish(GameEvent::ButtonClick);
+        }
+        clicked
+    }
+
+    fn scroll_by(&mut self, amount: i32) {
+        self.scroll = (self.scroll + amount).max(MIN_SCROLL);
+        self.camera
+            .snap_to(Vector::new(self.scroll as f32, 0.0), Vector::ZERO);
+    }
+
+    fn save(&self) {
+        if let Err(err) = browser::store_json(EDITOR_SEGMENT_KEY, &self.layout.segment) {
+            error!("Could not save the edited segment {:#?}", err);
+        }
+    }
+
+    fn export(&self) -> Result<()> {
+        self.save();
+        let segment = &self.layout.segment;
+        browser::download(
+            &format!("{}{}", segment.name, EXPORT_FILE_SUFFIX),
+            &browser::to_json(segment)?,
+        )
+    }
+
+    fn tiles(&self) -> impl Iterator<Item = (String, Rect)> {
+        self.sheet
+            .cell_names()
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| {
+                let tile = Rect::new_from_x_y(
+                    PALETTE.x() + SNAP + index as i32 * (PALETTE_TILE + SNAP / 2),
+                    PALETTE.y() + SNAP,
+                    PALETTE_TILE,
+                    PALETTE_TILE,
+                );
+                (name, tile)
+            })
+            .take_while(|(_, tile)| tile.right() < CLEAR_BRUSH_BUTTON.x())
+    }
+
+    fn tile_at(&self, point: Point) -> Option<String> {
+        self.tiles()
+            .find(|(_, tile)| tile.contains_point(&point))
+            .map(|(name, _)| name)
+    }
+
+    fn draw_grid(&self, renderer: &Renderer) {
+        let first = self.scroll.div_euclid(GRID) * GRID;
+        for x in (first..self.scroll + PALETTE.width + GRID).step_by(GRID as usize) {
+            let line = Rect::new_from_x_y(x, 0, 1, PALETTE.bottom());
+            renderer.fill_rect(&line, GRID_COLOR, GRID_ALPHA);
+        }
+        for y in (0..PALETTE.bottom()).step_by(GRID as usize) {
+            let line = Rect::new_from_x_y(self.scroll, y, PALETTE.width, 1);
+            renderer.fill_rect(&line, GRID_COLOR, GRID_ALPHA);
+        }
+        // The segment starts here
+        let origin = Rect::new_from_x_y(0, 0, 2, PALETTE.bottom());
+        renderer.fill_rect(&origin, ORIGIN_COLOR, 1.0);
+    }
+
+    fn draw_boxes(&self, renderer: &Renderer) {
+        for index in 0..self.layout.segment.obstacles.len() {
+            let selected = self.layout.selected == Some(index);
+            for bounding_box in self.layout.boxes(index) {
+                if selected {
+                    renderer.stroke_rect(&bounding_box, SELECTED_COLOR, 2.0);
+                    renderer.fill_rect(&handle(&bounding_box), SELECTED_COLOR, 1.0);
+                } else {
+                    renderer.stroke_rect(&bounding_box, BOX_COLOR, 1.0);
+                }
+            }
+        }
+    }
+
+    fn draw_palette(&self, renderer: &Renderer) -> Result<()> {
+        renderer.fill_rect(&PALETTE, PALETTE_COLOR, PALETTE_ALPHA);
+        for (name, tile) in self.tiles() {
+            if let Some(cell) = self.sheet.cell(&name) {
+                self.sheet.draw(renderer, &cell.frame(), &tile)?;
+            }
+        }
+        renderer.draw_styled_text(
+            &match self.tool {
+                Tool::Collectible => format!("Item: {}", self.collectible),
+                _ => format!("Platform: {}", self.brush.join(" ")),
+            },
+            &Point {
+                x: PALETTE.x() + SNAP,
+                y: PALETTE.y() - 2 * SNAP,
+            },
+            HINT_FONT,
+            HINT_COLOR,
+        )
+    }
+}
+
+fn toolbar_button(index: usize) -> Rect {
+    Rect::new_from_x_y(
+        SNAP + index as i32 * TOOLBAR_SPACING,
+        TOOLBAR_Y,
+        TOOLBAR_BUTTON_WIDTH,
+        TOOLBAR_BUTTON_HEIGHT,
+    )
+}
+
+/// The resize handle on the bottom right corner of `bounding_box`.
+fn handle(bounding_box: &Rect) -> Rect {
+    Rect::new_from_x_y(
+        bounding_box.right() - HANDLE_SIZE / 2,
+        bounding_box.bottom() - HANDLE_SIZE / 2,
+        HANDLE_SIZE,
+        HANDLE_SIZE,
+    )
+}
+
+fn snap(value: i32) -> i32 {
+    (value as f32 / SNAP as f32).round() as i32 * SNAP
+}
+
+fn snap_point(point: Point) -> Point {
+    Point {
+        x: snap(point.x),
+        y: snap(point.y),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STONE_BOX: Rect = Rect::new_from_x_y(0, 0, 90, 54);
+
+    fn layout() -> Layout {
+        let segment = SegmentData {
+            name: "test".to_string(),
+            obstacles: vec![
+                segment::stone(Point { x: 150, y: 546 }),
+                ObstacleData::Platform {
+                    position: Point { x: 370, y: 420 },
+                    sprites: vec!["13.png".to_string()],
+                    bounding_boxes: vec![Rect::new_from_x_y(0, 0, 60, 54)],
+                },
+            ],
+        };
+        Layout::new(segment, STONE_BOX)
+    }
+
+    #[test]
+    fn obstacles_are_dragged_to_snapped_positions() {
+        let mut layout = layout();
+
+        layout.press(Point { x: 160, y: 556 });
+        layout.drag_to(Point { x: 203, y: 548 });
+        layout.release();
+
+        assert_eq!(layout.selected, Some(0));
+        assert_eq!(
+            layout.segment.obstacles[0].position(),
+            Point { x: 190, y: 540 }
+        );
+        assert_eq!(layout.drag, None);
+    }
+
+    #[test]
+    fn corners_resize_the_selected_bounding_box() {
+        let mut layout = layout();
+        layout.press(Point { x: 160, y: 556 });
+        layout.release();
+
+        layout.press(Point { x: 240, y: 600 });
+        layout.drag_to(Point { x: 212, y: 581 });
+
+        assert_eq!(
+            layout.segment.obstacles[0].bounding_boxes(STONE_BOX),
+            vec![Rect::new_from_x_y(0, 0, 60, 40)]
+        );
+        assert_eq!(
+            layout.segment.obstacles[0].position(),
+            Point { x: 150, y: 546 }
+        );
+    }
+
+    #[test]
+    fn pressing_empty_space_deselects() {
+        let mut layout = layout();
+        layout.press(Point { x: 160, y: 556 });
+
+        layout.press(Point { x: 20, y: 200 });
+        layout.delete_selected();
+
+        assert_eq!(layout.selected, None);
+        assert_eq!(layout.segment.obstacles.len(), 2);
+    }
+}
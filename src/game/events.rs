@@ -0,0 +1,17 @@
+use serde::Deserialize;
+
+use crate::engine::events::EventQueue;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameEvent {
+    Jump,
+    Land,
+    Slide,
+    KnockOut,
+    Collect,
+    GameOver,
+    ButtonClick,
+}
+
+pub type GameEvents = EventQueue<GameEvent>;
@@ -0,0 +1,66 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::browser;
+
+const HIGH_SCORES_KEY: &str = "walk_the_dog.high_scores";
+const MAX_SCORES: usize = 5;
+
+/// The best scores so far, highest first.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HighScores {
+    scores: Vec<i32>,
+}
+
+impl HighScores {
+    /// Starts an empty table when nothing valid has been stored.
+    pub fn load() -> Self {
+        browser::load_json(HIGH_SCORES_KEY)
+            .unwrap_or_else(|err| {
+                error!("Could not load high scores {:#?}", err);
+                None
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        browser::store_json(HIGH_SCORES_KEY, self)
+    }
+
+    /// Records `score` and returns its rank, or `None` if it didn't make the table.
+    pub fn add(&mut self, score: i32) -> Option<usize> {
+        let rank = self
+            .scores
+            .iter()
+            .take_while(|best| **best >= score)
+            .count();
+        if rank >= MAX_SCORES {
+            return None;
+        }
+        self.scores.insert(rank, score);
+        self.scores.truncate(MAX_SCORES);
+        Some(rank)
+    }
+
+    pub fn scores(&self) -> &[i32] {
+        &self.scores
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_are_ranked_and_truncated() {
+        let mut high_scores = HighScores::default();
+        for score in [30, 10, 50, 20, 40] {
+            high_scores.add(score);
+        }
+
+        assert_eq!(high_scores.add(35), Some(2));
+        assert_eq!(high_scores.scores(), &[50, 40, 35, 30, 20]);
+        assert_eq!(high_scores.add(5), None);
+        assert_eq!(high_scores.add(20), None);
+    }
+}
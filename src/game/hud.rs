@@ -0,0 +1,74 @@
+use anyhow::Result;
+
+use crate::engine::{
+    tween::{Easing, Sequence, Tween, TweenStatus},
+    Point, Renderer,
+};
+
+const SCORE_POSITION: Point = Point { x: 20, y: 40 };
+const PIXELS_PER_POINT: f32 = 10.0;
+const MILESTONE: i32 = 100;
+const POP_SCALE: f32 = 1.5;
+
+/// Shows the distance run as a score that pops every `MILESTONE` points.
+pub struct Hud {
+    distance: f32,
+    bonus: i32,
+    pop: Option<Sequence<f32>>,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Hud {
+            distance: 0.0,
+            bonus: 0,
+            pop: None,
+        }
+    }
+
+    pub fn score(&self) -> i32 {
+        (self.distance / PIXELS_PER_POINT) as i32 + self.bonus
+    }
+
+    /// Jumps straight to `score` without a milestone pop.
+    pub fn set_score(&mut self, score: i32) {
+        self.distance = (score - self.bonus) as f32 * PIXELS_PER_POINT;
+    }
+
+    /// Points on top of the distance run, such as for collectibles.
+    pub fn add_bonus(&mut self, points: i32) {
+        self.bonus += points;
+    }
+
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+
+    pub fn add_distance(&mut self, distance: f32) {
+        let milestone = self.score() / MILESTONE;
+        self.distance += distance.max(0.0);
+        if self.score() / MILESTONE > milestone {
+            self.pop = Some(
+                Sequence::new(Tween::new(1.0, POP_SCALE, 8, Easing::BackOut)).then(Tween::new(
+                    POP_SCALE,
+                    1.0,
+                    12,
+                    Easing::QuadInOut,
+                )),
+            );
+        }
+    }
+
+    pub fn update(&mut self) {
+        if let Some(pop) = &mut self.pop {
+            if pop.update() == TweenStatus::Completed {
+                self.pop = None;
+            }
+        }
+    }
+
+    pub fn draw(&self, renderer: &Renderer) -> Result<()> {
+        let scale = self.pop.as_ref().map_or(1.0, |pop| pop.value());
+        renderer.draw_scaled_text(&format!("Score: {}", self.score()), &SCORE_POSITION, scale)
+    }
+}
@@ -0,0 +1,365 @@
+use super::{
+    audio_controls::{MUTE_KEY, VOLUME_DOWN_KEY, VOLUME_UP_KEY},
+    events::{GameEvent, GameEvents},
+    high_scores::HighScores,
+    settings::{Action, Settings},
+};
+use crate::engine::{
+    audio::Channel, console::CONSOLE_KEY, debug::DEBUG_KEY, hot_reload::RELOAD_KEY,
+    key_state::GAMEPAD_B, profiler::TRACE_KEY, ui::Ui, KeyState, Rect,
+};
+
+const BACK_KEYS: [&str; 2] = ["Escape", GAMEPAD_B];
+/// Keys that already do something everywhere, so an action bound to one would
+/// fire alongside it.
+const RESERVED_KEYS: [&str; 7] = [
+    MUTE_KEY,
+    VOLUME_DOWN_KEY,
+    VOLUME_UP_KEY,
+    CONSOLE_KEY,
+    DEBUG_KEY,
+    TRACE_KEY,
+    RELOAD_KEY,
+];
+
+const PANEL: Rect = Rect::new_from_x_y(140, 100, 320, 380);
+const TITLE: Rect = Rect::new_from_x_y(140, 110, 320, 40);
+const FIRST_ROW: i32 = 170;
+const ROW_HEIGHT: i32 = 50;
+const ROW_X: i32 = 180;
+const ROW_WIDTH: i32 = 240;
+const ROW_CONTENT_HEIGHT: i32 = 40;
+const BACK_BUTTON: Rect = Rect::new_from_x_y(220, 420, 160, 40);
+
+// The settings screen needs two columns and a taller panel
+const SETTINGS_PANEL: Rect = Rect::new_from_x_y(40, 100, 520, 460);
+const SETTINGS_TITLE: Rect = Rect::new_from_x_y(40, 110, 520, 40);
+const SETTINGS_BACK_BUTTON: Rect = Rect::new_from_x_y(220, 500, 160, 40);
+const SETTINGS_ROW_HEIGHT: i32 = 46;
+const SETTINGS_COLUMNS: [i32; 2] = [60, 310];
+const SETTINGS_COLUMN_WIDTH: i32 = 230;
+const VOLUMES: [(&str, Channel); 3] = [
+    ("Master", Channel::Master),
+    ("Music", Channel::Music),
+    ("Effects", Channel::Sfx),
+];
+
+const AUDIO_CONTROLS: [&str; 2] = ["M: sound on or off", "- and =: volume"];
+
+const CREDITS: [&str; 4] = [
+    "Red Hat Boy and tiles",
+    "by GameArt2D",
+    "Font and buttons",
+    "by Kenney",
+];
+
+enum Screen {
+    Main,
+    Settings,
+    HighScores(HighScores),
+    Controls,
+    Credits,
+}
+
+pub enum MenuAction {
+    Play,
+    Edit,
+}
+
+/// The title screen menu and its submenus, drawn with the canvas `Ui`.
+pub struct Menu {
+    screen: Screen,
+    events: GameEvents,
+    rebinding: Option<Action>,
+    // The reserved key last pressed while rebinding, shown as taken
+    taken: Option<String>,
+}
+
+impl Menu {
+    pub fn new(events: GameEvents) -> Self {
+        Menu {
+            screen: Screen::Main,
+            events,
+            rebinding: None,
+            taken: None,
+        }
+    }
+
+    /// Waiting for a key to bind, so keys mustn't move the focus.
+    pub fn is_rebinding(&self) -> bool {
+        self.rebinding.is_some()
+    }
+
+    pub fn update(
+        &mut self,
+        keystate: &KeyState,
+        ui: &mut Ui,
+        settings: &mut Settings,
+    ) -> Option<MenuAction> {
+        match &self.screen {
+            Screen::Main => return self.main(ui),
+            Screen::Settings => {
+                self.settings(keystate, ui, settings);
+                return None;
+            }
+            Screen::HighScores(high_scores) => {
+                ui.panel(PANEL);
+                ui.label("High scores", TITLE);
+                if high_scores.scores().is_empty() {
+                    ui.label("No scores yet", row(0));
+                }
+                for (rank, score) in high_scores.scores().iter().enumerate() {
+                    ui.label(&format!("{}. {}", rank + 1, score), row(rank as i32));
+                }
+            }
+            Screen::Controls => {
+                let controls: Vec<String> = Action::ALL
+                    .iter()
+                    .map(|action| format!("{}: {}", settings.controls.key(*action), action.name()))
+                    .chain(AUDIO_CONTROLS.iter().map(|line| line.to_string()))
+                    .collect();
+                lines(ui, "Controls", &controls);
+            }
+            Screen::Credits => lines(ui, "Credits", &CREDITS),
+        }
+        self.back(keystate, ui, BACK_BUTTON);
+        None
+    }
+
+    fn main(&mut self, ui: &mut Ui) -> Option<MenuAction> {
+        ui.panel(PANEL);
+        ui.label("Walk the Dog", TITLE);
+        if self.button(ui, "Play", row(0)) {
+            return Some(MenuAction::Play);
+        }
+        if self.button(ui, "Settings", row(1)) {
+            self.screen = Screen::Settings;
+        }
+        if self.button(ui, "High scores", row(2)) {
+            self.screen = Screen::HighScores(HighScores::load());
+        }
+        if self.button(ui, "Controls", row(3)) {
+            self.screen = Screen::Controls;
+        }
+        if self.button(ui, "Credits", row(4)) {
+            self.screen = Screen::Credits;
+        }
+        // Authoring segments is for development builds only
+        if cfg!(debug_assertions) && self.button(ui, "Segment editor", row(5)) {
+            return Some(MenuAction::Edit);
+        }
+        None
+    }
+
+    fn settings(&mut self, keystate: &KeyState, ui: &mut Ui, settings: &mut Settings) {
+        ui.panel(SETTINGS_PANEL);
+        ui.label("Settings", SETTINGS_TITLE);
+        if let Some(action) = self.rebinding {
+            // The first free key pressed is bound, except the back keys which cancel
+            if let Some(key) = keystate.just_pressed_keys().next() {
+                if RESERVED_KEYS.contains(&key) {
+                    self.taken = Some(key.to_string());
+                } else {
+                    if !BACK_KEYS.contains(&key) {
+                        settings.controls.bind(action, key);
+                    }
+                    self.rebinding = None;
+                    self.taken = None;
+                }
+                return;
+            }
+        }
+
+        // Left column: audio, controls and difficulty
+        for (index, (name, channel)) in VOLUMES.into_iter().enumerate() {
+            let mut volume = settings.audio.volume(channel);
+            if ui.slider(name, settings_row(0, index), &mut volume) {
+                settings.audio.set_volume(channel, volume);
+            }
+        }
+        for (index, action) in Action::ALL.into_iter().enumerate() {
+            let label = if self.rebinding == Some(action) {
+                match &self.taken {
+                    Some(key) => format!("{}: {} is taken", action.name(), key),
+                    None => format!("{}: press a key", action.name()),
+                }
+            } else {
+                format!("{}: {}", action.name(), settings.controls.key(action))
+            };
+            if self.button(ui, &label, settings_row(0, VOLUMES.len() + index)) {
+                self.rebinding = Some(action);
+                self.taken = None;
+            }
+        }
+        let label = format!("Difficulty: {}", settings.difficulty.name());
+        if self.button(
+            ui,
+            &label,
+            settings_row(0, VOLUMES.len() + Action::ALL.len()),
+        ) {
+            settings.difficulty = settings.difficulty.next();
+        }
+
+        // Right column: motion and display
+        let toggles = [
+            ("Reduced motion", &mut settings.reduced_motion),
+            ("Screen shake", &mut settings.effects.screen_shake),
+            ("Hit stop", &mut settings.effects.hit_stop),
+            ("Flash", &mut settings.effects.flash),
+            ("Show FPS", &mut settings.show_fps),
+        ];
+        let toggle_count = toggles.len();
+        for (index, (name, enabled)) in toggles.into_iter().enumerate() {
+            let label = format!("{}: {}", name, if *enabled { "on" } else { "off" });
+            if self.button(ui, &label, settings_row(1, index)) {
+                *enabled = !*enabled;
+            }
+        }
+        let label = format!("Palette: {}", settings.palette.name());
+        if self.button(ui, &label, settings_row(1, toggle_count)) {
+            settings.palette = settings.palette.next();
+        }
+
+        if self.rebinding.is_none() {
+            self.back(keystate, ui, SETTINGS_BACK_BUTTON);
+        }
+    }
+
+    fn back(&mut self, keystate: &KeyState, ui: &mut Ui, area: Rect) {
+        let back = BACK_KEYS.iter().any(|key| keystate.is_just_pressed(key));
+        if self.button(ui, "Back", area) || back {
+            self.screen = Screen::Main;
+        }
+    }
+
+    fn button(&self, ui: &mut Ui, text: &str, area: Rect) -> bool {
+        let clicked = ui.button(text, area);
+        if clicked {
+            self.events.publish(GameEvent::ButtonClick);
+        }
+        clicked
+    }
+}
+
+fn lines(ui: &mut Ui, title: &str, lines: &[impl AsRef<str>]) {
+    ui.panel(PANEL);
+    ui.label(title, TITLE);
+    for (index, line) in lines.iter().enumerate() {
+        ui.label(line.as_ref(), row(index as i32));
+    }
+}
+
+fn row(index: i32) -> Rect {
+    Rect::new_from_x_y(
+        ROW_X,
+        FIRST_ROW + index * ROW_HEIGHT,
+        ROW_WIDTH,
+        ROW_CONTENT_HEIGHT,
+    )
+}
+
+fn settings_row(column: usize, index: usize) -> Rect {
+    Rect::new_from_x_y(
+        SETTINGS_COLUMNS[column],
+        FIRST_ROW + index as i32 * SETTINGS_ROW_HEIGHT,
+        SETTINGS_COLUMN_WIDTH,
+        ROW_CONTENT_HEIGHT,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(key: &str) -> KeyState {
+        let mut keystate = KeyState::new();
+        keystate.set_pressed(key);
+        keystate
+    }
+
+    fn click(area: Rect) -> KeyState {
+        let mut keystate = KeyState::new();
+        keystate.set_pointer_down(area.position);
+        keystate
+    }
+
+    #[test]
+    fn submenus_are_entered_and_left_with_the_keyboard() {
+        let mut menu = Menu::new(GameEvents::new());
+        let mut ui = Ui::new();
+        let mut settings = Settings::default();
+        ui.set_keyboard_navigation(true);
+        let mut update = |menu: &mut Menu, keystate: &KeyState| {
+            ui.begin(keystate);
+            menu.update(keystate, &mut ui, &mut settings)
+        };
+
+        update(&mut menu, &KeyState::new());
+        update(&mut menu, &KeyState::new());
+        for _ in 0..3 {
+            update(&mut menu, &press("ArrowDown"));
+        }
+        update(&mut menu, &press("Enter"));
+        assert!(matches!(menu.screen, Screen::Controls));
+
+        update(&mut menu, &press(GAMEPAD_B));
+        assert!(matches!(menu.screen, Screen::Main));
+
+        update(&mut menu, &press("ArrowUp"));
+        assert!(matches!(
+            update(&mut menu, &press("Enter")),
+            Some(MenuAction::Play)
+        ));
+    }
+
+    #[test]
+    fn the_next_key_pressed_is_bound() {
+        let mut menu = Menu::new(GameEvents::new());
+        menu.screen = Screen::Settings;
+        let mut ui = Ui::new();
+        let mut settings = Settings::default();
+        let jump_button = settings_row(0, VOLUMES.len() + 1);
+        let mut update = |menu: &mut Menu, keystate: &KeyState| {
+            ui.begin(keystate);
+            menu.update(keystate, &mut ui, &mut settings);
+        };
+
+        update(&mut menu, &click(jump_button));
+        assert_eq!(menu.rebinding, Some(Action::Jump));
+        update(&mut menu, &KeyState::new());
+        update(&mut menu, &press("Escape"));
+        assert!(!menu.is_rebinding());
+        assert!(matches!(menu.screen, Screen::Settings));
+
+        update(&mut menu, &click(jump_button));
+        update(&mut menu, &press("KeyW"));
+        assert!(!menu.is_rebinding());
+        assert_eq!(settings.controls.jump, "KeyW");
+    }
+
+    #[test]
+    fn reserved_keys_are_not_bound() {
+        let mut menu = Menu::new(GameEvents::new());
+        menu.screen = Screen::Settings;
+        let mut ui = Ui::new();
+        let mut settings = Settings::default();
+        let slide_button = settings_row(0, VOLUMES.len() + 2);
+        let mut update = |menu: &mut Menu, keystate: &KeyState| {
+            ui.begin(keystate);
+            menu.update(keystate, &mut ui, &mut settings);
+        };
+
+        update(&mut menu, &click(slide_button));
+        assert_eq!(menu.rebinding, Some(Action::Slide));
+        for key in RESERVED_KEYS {
+            update(&mut menu, &press(key));
+            assert!(menu.is_rebinding());
+            assert_eq!(menu.taken.as_deref(), Some(key));
+        }
+
+        update(&mut menu, &press("KeyS"));
+        assert!(!menu.is_rebinding());
+        assert_eq!(menu.taken, None);
+        assert_eq!(settings.controls.slide, "KeyS");
+    }
+}
@@ -0,0 +1,40 @@
+use anyhow::Result;
+
+use crate::engine::audio::{Audio, MusicController, Sound};
+
+const CROSSFADE_SECONDS: f64 = 1.5;
+
+/// The background song, which plays from Ready through Walking and fades out
+/// on GameOver. There is only the one track so far.
+pub struct Music {
+    controller: MusicController,
+    song: Sound,
+}
+
+impl Music {
+    pub async fn load(audio: Audio) -> Result<Self> {
+        let song = audio
+            .load_sound("assets/sounds/background_song.mp3")
+            .await?;
+        Ok(Music {
+            controller: MusicController::new(audio),
+            song,
+        })
+    }
+
+    /// Fades the song in, unless it is already playing.
+    pub fn start(&mut self) {
+        if let Err(err) = self
+            .controller
+            .crossfade_to("background", &self.song, CROSSFADE_SECONDS)
+        {
+            error!("Error starting the music {:#?}", err);
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Err(err) = self.controller.stop(CROSSFADE_SECONDS) {
+            error!("Error stopping the music {:#?}", err);
+        }
+    }
+}
@@ -1,12 +1,13 @@
 use anyhow::Result;
 
-use crate::engine::Renderer;
+use crate::engine::{Rect, Renderer};
 
 use super::redhatboy::RedHatBoy;
 
 pub trait Obstacle {
+    /// Covers everything the obstacle can collide with, for the broad phase.
+    fn bounding_box(&self) -> Rect;
     fn check_intersection(&self, boy: &mut RedHatBoy);
     fn draw(&self, renderer: &Renderer) -> Result<()>;
-    fn move_horizontally(&mut self, dx: i16);
-    fn right(&self) -> i16;
-}
\ No newline at end of file
+    fn right(&self) -> i32;
+}
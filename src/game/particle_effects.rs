@@ -0,0 +1,103 @@
+use anyhow::Result;
+
+use super::{events::GameEvent, redhatboy::RedHatBoy};
+use crate::engine::{
+    particles::{Emitter, EmitterConfig, ParticleLook},
+    Point, Renderer,
+};
+
+const DUST_INTERVAL: u8 = 6;
+const STAR_OFFSET: Point = Point { x: 0, y: -70 };
+
+/// The particle emitters used by the game, fed from game events and the boy's state.
+pub struct ParticleEffects {
+    dust: Emitter,
+    puffs: Emitter,
+    stars: Emitter,
+    dust_timer: u8,
+}
+
+impl ParticleEffects {
+    pub fn new() -> Self {
+        ParticleEffects {
+            dust: Emitter::new(
+                EmitterConfig {
+                    burst: 2,
+                    velocity_x: -1.5..=-0.5,
+                    velocity_y: -1.2..=-0.4,
+                    gravity: 0.05,
+                    lifetime: 15..=25,
+                    fade: true,
+                    look: ParticleLook::Square {
+                        color: "#c8b28a",
+                        size: 4,
+                    },
+                },
+                24,
+            ),
+            puffs: Emitter::new(
+                EmitterConfig {
+                    burst: 8,
+                    velocity_x: -2.5..=2.5,
+                    velocity_y: -1.5..=-0.2,
+                    gravity: 0.08,
+                    lifetime: 12..=20,
+                    fade: true,
+                    look: ParticleLook::Square {
+                        color: "#e6dccb",
+                        size: 6,
+                    },
+                },
+                32,
+            ),
+            stars: Emitter::new(
+                EmitterConfig {
+                    burst: 10,
+                    velocity_x: -3.0..=3.0,
+                    velocity_y: -5.0..=-2.0,
+                    gravity: 0.25,
+                    lifetime: 30..=45,
+                    fade: true,
+                    look: ParticleLook::Square {
+                        color: "gold",
+                        size: 5,
+                    },
+                },
+                20,
+            ),
+            dust_timer: 0,
+        }
+    }
+
+    pub fn handle(&mut self, event: GameEvent, boy: &RedHatBoy) {
+        match event {
+            GameEvent::Land => self.puffs.emit(boy.feet()),
+            GameEvent::KnockOut => self.stars.emit(boy.feet() + STAR_OFFSET),
+            _ => {}
+        }
+    }
+
+    pub fn update(&mut self, boy: &RedHatBoy) {
+        if boy.is_running() {
+            self.dust_timer = (self.dust_timer + 1) % DUST_INTERVAL;
+            if self.dust_timer == 0 {
+                self.dust.emit(boy.feet());
+            }
+        }
+        self.dust.update();
+        self.puffs.update();
+        self.stars.update();
+    }
+
+    pub fn clear(&mut self) {
+        self.dust.clear();
+        self.puffs.clear();
+        self.stars.clear();
+    }
+
+    pub fn draw(&self, renderer: &Renderer) -> Result<()> {
+        self.dust.draw(renderer)?;
+        self.puffs.draw(renderer)?;
+        self.stars.draw(renderer)
+    }
+}
@@ -2,44 +2,47 @@ use std::rc::Rc;
 
 use anyhow::Result;
 
-use crate::engine::{Cell, Point, Rect, Renderer, SpriteSheet};
+use crate::engine::{Point, Rect, Renderer, SpriteSheet, Vector};
 
 use super::{Obstacle, RedHatBoy};
 
+/// Slack for the boy's bounding box being rounded to whole pixels.
+const LANDING_TOLERANCE: f32 = 2.0;
+const TOP: Point = Point { x: 0, y: -1 };
+
 pub struct Platform {
     bounding_boxes: Vec<Rect>,
-    position: Point,
+    position: Vector,
     sheet: Rc<SpriteSheet>,
-    sprites: Vec<Cell>,
+    // Looked up as they are drawn, so a reloaded sheet shows straight away
+    sprite_names: Vec<String>,
 }
 
 impl Platform {
     pub fn new(
         bounding_boxes: &[Rect],
-        position: Point,
+        position: Vector,
         sheet: Rc<SpriteSheet>,
         sprite_names: &[&str],
     ) -> Self {
+        // Collisions use whole pixels, as the boy's bounding box does
+        let corner = position.to_point();
         let bounding_boxes = bounding_boxes
             .iter()
             .map(|bounding_box| {
                 Rect::new_from_x_y(
-                    bounding_box.x() + position.x,
-                    bounding_box.y() + position.y,
+                    bounding_box.x() + corner.x,
+                    bounding_box.y() + corner.y,
                     bounding_box.width,
                     bounding_box.height,
                 )
             })
             .collect();
-        let sprites = sprite_names
-            .iter()
-            .filter_map(|sprite_name| sheet.cell(sprite_name).cloned())
-            .collect();
         Platform {
             bounding_boxes,
             position,
             sheet,
-            sprites,
+            sprite_names: sprite_names.iter().map(ToString::to_string).collect(),
         }
     }
 
@@ -49,14 +52,30 @@ impl Platform {
 }
 
 impl Obstacle for Platform {
+    fn bounding_box(&self) -> Rect {
+        self.bounding_boxes()
+            .iter()
+            .copied()
+            .reduce(|bounds, bounding_box| bounds.union(&bounding_box))
+            .unwrap_or_default()
+    }
+
     fn check_intersection(&self, boy: &mut RedHatBoy) {
-        if let Some(box_to_land_on) = self
+        let first_hit = self
             .bounding_boxes()
             .iter()
-            .find(|&bounding_box| boy.bounding_box().intersects(bounding_box))
-        {
-            if boy.velocity_y() > 0 && boy.pos_y() < self.position.y {
-                boy.land_on(box_to_land_on.y());
+            .filter_map(|bounding_box| boy.sweep(bounding_box).map(|hit| (bounding_box, hit)))
+            .min_by(|(_, hit), (_, other_hit)| hit.time.total_cmp(&other_hit.time));
+
+        if let Some((box_to_land_on, hit)) = first_hit {
+            let landed = if hit.time > 0.0 {
+                hit.normal == TOP
+            } else {
+                // Already overlapping before the move, as when running along the top
+                landed_from_above(boy, box_to_land_on)
+            };
+            if boy.velocity_y() > 0.0 && landed {
+                boy.land_on(box_to_land_on.y() as f32);
             } else {
                 boy.knock_out();
             }
@@ -64,41 +83,45 @@ impl Obstacle for Platform {
     }
 
     fn draw(&self, renderer: &Renderer) -> Result<()> {
+        let position = self.position.to_point();
         let mut dx = 0;
 
-        self.sprites.iter().try_for_each(|sprite| -> Result<()> {
+        let mut sprites = self
+            .sprite_names
+            .iter()
+            .filter_map(|sprite_name| self.sheet.cell(sprite_name));
+        sprites.try_for_each(|sprite| -> Result<()> {
             self.sheet.draw(
                 renderer,
                 &sprite.frame(),
-                &sprite.destination(&Point {
-                    x: self.position.x + dx,
-                    y: self.position.y,
-                }),
+                &sprite.destination(&(position + Point { x: dx, y: 0 })),
             )?;
             dx += sprite.frame().width;
             Ok(())
         })?;
 
-        if cfg!(debug_assertions) {
-            for rect in self.bounding_boxes() {
-                renderer.draw_rect(rect);
-            }
+        for rect in self.bounding_boxes() {
+            renderer.draw_debug_rect(rect);
         }
 
         Ok(())
     }
 
-    fn move_horizontally(&mut self, dx: i16) {
-        self.position.x += dx;
-        self.bounding_boxes.iter_mut().for_each(|bounding_box| {
-            bounding_box.set_x(bounding_box.x() + dx);
-        });
-    }
-
-    fn right(&self) -> i16 {
+    fn right(&self) -> i32 {
         self.bounding_boxes()
             .last()
             .unwrap_or(&Rect::default())
             .right()
     }
 }
+
+/// Sinking into the top by no more than this update's fall means the boy came
+/// from above, however little of the platform's width it caught.
+fn landed_from_above(boy: &RedHatBoy, bounding_box: &Rect) -> bool {
+    boy.bounding_box()
+        .intersection(bounding_box)
+        .is_some_and(|overlap| {
+            overlap.y() == bounding_box.y()
+                && overlap.height as f32 <= boy.velocity_y() + LANDING_TOLERANCE
+        })
+}
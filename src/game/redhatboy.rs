@@ -1,91 +1,187 @@
+use std::{cell, rc::Rc};
+
 use anyhow::Result;
+pub use red_hat_boy_states::Physics;
 use red_hat_boy_states::{
     Falling, FallingEndState, Idle, Jumping, JumpingEndState, KnockedOut, RedHatBoyContext,
     RedHatBoyState, Running, Sliding, SlidingEndState,
 };
 
 use crate::engine::{
-    audio::{Audio, Sound},
-    Cell, Rect, Renderer, SpriteSheet,
+    error::EngineError, rect::Sweep, Cell, Point, Rect, Renderer, SpriteSheet, Vector,
 };
 
+use super::events::{GameEvent, GameEvents};
+
 pub struct RedHatBoy {
     state_machine: RedHatBoyStateMachine,
-    sprite_sheet: SpriteSheet,
+    sprite_sheet: Rc<SpriteSheet>,
+    last_move: Vector,
+    invincible: bool,
+    // A missing frame would otherwise be reported on every collision check
+    reported_missing_frame: cell::Cell<bool>,
 }
 
 impl RedHatBoy {
-    pub fn new(sprite_sheet: SpriteSheet, audio: Audio, jump_sound: Sound) -> Self {
+    pub fn new(sprite_sheet: Rc<SpriteSheet>, events: GameEvents) -> Self {
         RedHatBoy {
-            state_machine: RedHatBoyStateMachine::Idle(RedHatBoyState::new(audio, jump_sound)),
+            state_machine: RedHatBoyStateMachine::Idle(RedHatBoyState::new(events)),
             sprite_sheet,
+            last_move: Vector::ZERO,
+            invincible: false,
+            reported_missing_frame: cell::Cell::new(false),
         }
     }
 
+    /// Starts over, keeping the tuning and invincibility from the developer console.
     pub fn reset(boy: Self) -> Self {
-        RedHatBoy::new(
-            boy.sprite_sheet,
-            boy.state_machine.context().audio.clone(),
-            boy.state_machine.context().jump_sound.clone(),
-        )
+        let physics = boy.physics();
+        let mut new_boy =
+            RedHatBoy::new(boy.sprite_sheet, boy.state_machine.context().events.clone());
+        new_boy.set_physics(physics);
+        new_boy.invincible = boy.invincible;
+        new_boy
     }
 
     pub fn update(&mut self) {
+        let start = self.position();
         self.state_machine = self.state_machine.clone().update();
+        self.last_move = self.position() - start;
     }
 
     pub fn draw(&self, renderer: &Renderer) -> Result<()> {
-        let sprite = self.sprite().expect("Cell not found");
+        let sprite = self.sprite()?;
 
-        if cfg!(debug_assertions) {
-            renderer.draw_rect(&self.bounding_box());
-        }
+        renderer.draw_debug_rect(&self.bounding_box());
         self.sprite_sheet
             .draw(renderer, &sprite.frame(), &self.destination_box())
     }
 
+    /// A missing frame is reported once and gives an empty box, so the boy
+    /// can't collide with anything.
     pub fn destination_box(&self) -> Rect {
-        let sprite = self.sprite().expect("Cell not found");
-        sprite.destination(&self.state_machine.context().position)
+        match self.sprite() {
+            Ok(sprite) => sprite.destination(&self.state_machine.context().position.to_point()),
+            Err(err) => {
+                if !self.reported_missing_frame.replace(true) {
+                    warn!("No destination box for Red Hat Boy: {:#}", err);
+                }
+                Rect::default()
+            }
+        }
     }
 
     pub fn bounding_box(&self) -> Rect {
-        const X_OFFSET: i16 = 18;
-        const Y_OFFSET: i16 = 14;
-        const WIDTH_OFFSET: i16 = 28;
+        const X_OFFSET: i32 = 18;
+        const Y_OFFSET: i32 = 14;
+        const WIDTH_OFFSET: i32 = 28;
+        let destination_box = self.destination_box();
         Rect::new_from_x_y(
-            self.destination_box().x() + X_OFFSET,
-            self.destination_box().y() + Y_OFFSET,
-            self.destination_box().width - WIDTH_OFFSET,
-            self.destination_box().height - Y_OFFSET,
+            destination_box.x() + X_OFFSET,
+            destination_box.y() + Y_OFFSET,
+            destination_box.width - WIDTH_OFFSET,
+            destination_box.height - Y_OFFSET,
+        )
+    }
+
+    /// Sweeps the bounding box along the last update's move, so fast moves can't
+    /// skip over thin obstacles.
+    pub fn sweep(&self, other: &Rect) -> Option<Sweep> {
+        self.start_box().sweep(self.last_move, other)
+    }
+
+    /// Everything the bounding box passed through during the last update.
+    pub fn swept_box(&self) -> Rect {
+        self.start_box().union(&self.bounding_box())
+    }
+
+    fn start_box(&self) -> Rect {
+        let bounding_box = self.bounding_box();
+        Rect::new(
+            bounding_box.position - self.last_move.to_point(),
+            bounding_box.width,
+            bounding_box.height,
         )
     }
 
-    pub fn walking_speed(&self) -> i16 {
-        self.state_machine.context().velocity.x
+    pub fn position(&self) -> Vector {
+        self.state_machine.context().position
     }
 
-    pub fn pos_y(&self) -> i16 {
-        self.state_machine.context().position.y
+    pub fn velocity(&self) -> Vector {
+        self.state_machine.context().velocity
     }
 
-    pub fn velocity_y(&self) -> i16 {
+    pub fn velocity_y(&self) -> f32 {
         self.state_machine.context().velocity.y
     }
 
+    pub fn state_name(&self) -> &'static str {
+        self.state_machine.name()
+    }
+
     pub fn knocked_out(&self) -> bool {
         self.state_machine.knocked_out()
     }
 
+    pub fn physics(&self) -> Physics {
+        self.state_machine.context().physics
+    }
+
+    /// A new running speed applies straight away while the boy is moving.
+    pub fn set_physics(&mut self, physics: Physics) {
+        let context = self.state_machine.context_mut();
+        context.physics = physics;
+        if context.velocity.x != 0.0 {
+            context.velocity.x = physics.running_speed;
+        }
+    }
+
+    pub fn is_invincible(&self) -> bool {
+        self.invincible
+    }
+
+    pub fn set_invincible(&mut self, invincible: bool) {
+        self.invincible = invincible;
+    }
+
+    /// Moves the boy forward without touching anything on the way.
+    pub fn move_forward(&mut self, distance: f32) {
+        self.state_machine.context_mut().position.x += distance;
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.state_machine, RedHatBoyStateMachine::Running(_))
+    }
+
+    /// Where the boy touches the ground, for effects spawned at the boy's feet.
+    pub fn feet(&self) -> Point {
+        let bounding_box = self.bounding_box();
+        Point {
+            x: bounding_box.x() + bounding_box.width / 2,
+            y: bounding_box.bottom(),
+        }
+    }
+
     pub fn jump(&mut self) {
         self.state_machine = self.state_machine.clone().transition(Event::Jump);
     }
 
     pub fn knock_out(&mut self) {
+        if self.invincible {
+            return;
+        }
         self.state_machine = self.state_machine.clone().transition(Event::KnockOut);
     }
 
-    pub fn land_on(&mut self, position: i16) {
+    pub fn collect(&mut self) {
+        self.state_machine
+            .context()
+            .events
+            .publish(GameEvent::Collect);
+    }
+
+    pub fn land_on(&mut self, position: f32) {
         self.state_machine = self.state_machine.clone().transition(Event::Land(position));
     }
 
@@ -105,8 +201,12 @@ impl RedHatBoy {
         )
     }
 
-    fn sprite(&self) -> Option<&Cell> {
-        self.sprite_sheet.cell(&self.frame_name())
+    fn sprite(&self) -> Result<Cell> {
+        let frame_name = self.frame_name();
+        Ok(self
+            .sprite_sheet
+            .cell(&frame_name)
+            .ok_or(EngineError::MissingSprite(frame_name))?)
     }
 }
 
@@ -186,7 +286,7 @@ impl From<SlidingEndState> for RedHatBoyStateMachine {
 pub enum Event {
     Jump,
     KnockOut,
-    Land(i16),
+    Land(f32),
     Run,
     Slide,
     Update,
@@ -219,6 +319,17 @@ impl RedHatBoyStateMachine {
         }
     }
 
+    fn name(&self) -> &'static str {
+        match self {
+            RedHatBoyStateMachine::Falling(_) => "Falling",
+            RedHatBoyStateMachine::Idle(_) => "Idle",
+            RedHatBoyStateMachine::Jumping(_) => "Jumping",
+            RedHatBoyStateMachine::KnockedOut(_) => "KnockedOut",
+            RedHatBoyStateMachine::Running(_) => "Running",
+            RedHatBoyStateMachine::Sliding(_) => "Sliding",
+        }
+    }
+
     fn frame_name(&self) -> &str {
         match self {
             RedHatBoyStateMachine::Falling(state) => state.frame_name(),
@@ -241,6 +352,17 @@ impl RedHatBoyStateMachine {
         }
     }
 
+    fn context_mut(&mut self) -> &mut RedHatBoyContext {
+        match self {
+            RedHatBoyStateMachine::Falling(state) => state.context_mut(),
+            RedHatBoyStateMachine::Idle(state) => state.context_mut(),
+            RedHatBoyStateMachine::Jumping(state) => state.context_mut(),
+            RedHatBoyStateMachine::KnockedOut(state) => state.context_mut(),
+            RedHatBoyStateMachine::Running(state) => state.context_mut(),
+            RedHatBoyStateMachine::Sliding(state) => state.context_mut(),
+        }
+    }
+
     fn update(self) -> Self {
         self.transition(Event::Update)
     }
@@ -252,18 +374,18 @@ impl RedHatBoyStateMachine {
 
 mod red_hat_boy_states {
     use crate::{
-        engine::{
-            audio::{Audio, Sound},
-            Point,
+        engine::Vector,
+        game::{
+            events::{GameEvent, GameEvents},
+            HEIGHT,
         },
-        game::HEIGHT,
     };
 
-    const FLOOR: i16 = 479;
-    const PLAYER_HEIGHT: i16 = HEIGHT - FLOOR;
-    const STARTING_POINT: i16 = -20;
-    const GRAVITY: i16 = 1;
-    const TERMINAL_VELOCITY: i16 = 20;
+    const FLOOR: f32 = 479.0;
+    const PLAYER_HEIGHT: f32 = HEIGHT as f32 - FLOOR;
+    const STARTING_POINT: f32 = -20.0;
+    const GRAVITY: f32 = 1.0;
+    const TERMINAL_VELOCITY: f32 = 20.0;
 
     const FALLING_FRAME_NAME: &str = "Dead";
     const FALLING_FRAMES: u8 = 29;
@@ -271,14 +393,34 @@ mod red_hat_boy_states {
     const IDLE_FRAMES: u8 = 29;
     const JUMPING_FRAME_NAME: &str = "Jump";
     const JUMPING_FRAMES: u8 = 35;
-    const JUMPING_SPEED: i16 = -25;
+    const JUMPING_SPEED: f32 = -25.0;
     const KNOCKED_OUT_FRAME_NAME: &str = "Dead";
     const RUNNING_FRAME_NAME: &str = "Run";
     const RUNNING_FRAMES: u8 = 23;
-    const RUNNING_SPEED: i16 = 3;
+    const RUNNING_SPEED: f32 = 3.0;
     const SLIDING_FRAME_NAME: &str = "Slide";
     const SLIDING_FRAMES: u8 = 14;
 
+    /// The tunable parts of the boy's movement, starting from the constants above.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Physics {
+        pub gravity: f32,
+        pub terminal_velocity: f32,
+        pub jumping_speed: f32,
+        pub running_speed: f32,
+    }
+
+    impl Default for Physics {
+        fn default() -> Self {
+            Physics {
+                gravity: GRAVITY,
+                terminal_velocity: TERMINAL_VELOCITY,
+                jumping_speed: JUMPING_SPEED,
+                running_speed: RUNNING_SPEED,
+            }
+        }
+    }
+
     #[derive(Clone)]
     pub struct RedHatBoyState<S> {
         context: RedHatBoyContext,
@@ -288,10 +430,10 @@ mod red_hat_boy_states {
     #[derive(Clone)]
     pub struct RedHatBoyContext {
         pub frame: u8,
-        pub position: Point,
-        pub velocity: Point,
-        pub audio: Audio,
-        pub jump_sound: Sound,
+        pub position: Vector,
+        pub velocity: Vector,
+        pub events: GameEvents,
+        pub physics: Physics,
     }
 
     impl RedHatBoyContext {
@@ -301,21 +443,22 @@ mod red_hat_boy_states {
             } else {
                 self.frame = 0;
             }
-            if self.velocity.y < TERMINAL_VELOCITY {
-                self.velocity.y += GRAVITY;
+            if self.velocity.y < self.physics.terminal_velocity {
+                self.velocity.y += self.physics.gravity;
             }
 
-            self.position.y += self.velocity.y;
+            self.position += self.velocity;
             if self.position.y > FLOOR {
-                self.velocity.y = 0;
+                self.velocity.y = 0.0;
                 self.position.y = FLOOR;
             }
             self
         }
 
-        fn set_on(mut self, position: i16) -> Self {
+        fn set_on(mut self, position: f32) -> Self {
             let position = position - PLAYER_HEIGHT;
             self.position.y = position;
+            self.velocity.y = 0.0;
             self
         }
 
@@ -325,25 +468,23 @@ mod red_hat_boy_states {
         }
 
         fn jump(mut self) -> Self {
-            self.velocity.y = JUMPING_SPEED;
+            self.velocity.y = self.physics.jumping_speed;
             self
         }
 
         fn run(mut self) -> Self {
-            self.velocity.x = RUNNING_SPEED;
+            self.velocity.x = self.physics.running_speed;
             self
         }
 
         fn stop(mut self) -> Self {
-            self.velocity.x = 0;
-            self.velocity.y = 0;
+            self.velocity.x = 0.0;
+            self.velocity.y = 0.0;
             self
         }
 
-        fn play_jump_sound(self) -> Self {
-            if let Err(err) = self.audio.play_sound(&self.jump_sound) {
-                log!("Error playing jump sound {:?}", err);
-            }
+        fn publish(self, event: GameEvent) -> Self {
+            self.events.publish(event);
             self
         }
     }
@@ -394,17 +535,14 @@ mod red_hat_boy_states {
     }
 
     impl RedHatBoyState<Idle> {
-        pub fn new(audio: Audio, jump_sound: Sound) -> Self {
+        pub fn new(events: GameEvents) -> Self {
             RedHatBoyState {
                 context: RedHatBoyContext {
                     frame: 0,
-                    position: Point {
-                        x: STARTING_POINT,
-                        y: FLOOR,
-                    },
-                    velocity: Point { x: 0, y: 0 },
-                    audio,
-                    jump_sound,
+                    position: Vector::new(STARTING_POINT, FLOOR),
+                    velocity: Vector::default(),
+                    events,
+                    physics: Physics::default(),
                 },
                 _state: Idle,
             }
@@ -436,7 +574,7 @@ mod red_hat_boy_states {
             self.context = self.context.update(JUMPING_FRAMES);
 
             if self.context.position.y >= FLOOR {
-                JumpingEndState::Complete(self.land_on(HEIGHT))
+                JumpingEndState::Complete(self.land_on(HEIGHT as f32))
             } else {
                 JumpingEndState::Jumping(self)
             }
@@ -444,15 +582,23 @@ mod red_hat_boy_states {
 
         pub fn knock_out(self) -> RedHatBoyState<Falling> {
             RedHatBoyState {
-                context: self.context.reset_frame().stop(),
+                context: self
+                    .context
+                    .reset_frame()
+                    .stop()
+                    .publish(GameEvent::KnockOut),
                 _state: Falling,
             }
         }
 
-        pub fn land_on(self, position: i16) -> RedHatBoyState<Running> {
-            log!("Landing at position {}", position);
+        pub fn land_on(self, position: f32) -> RedHatBoyState<Running> {
+            debug!("Landing at position {}", position);
             RedHatBoyState {
-                context: self.context.reset_frame().set_on(position),
+                context: self
+                    .context
+                    .reset_frame()
+                    .set_on(position)
+                    .publish(GameEvent::Land),
                 _state: Running,
             }
         }
@@ -481,19 +627,23 @@ mod red_hat_boy_states {
 
         pub fn jump(self) -> RedHatBoyState<Jumping> {
             RedHatBoyState {
-                context: self.context.reset_frame().jump().play_jump_sound(),
+                context: self.context.reset_frame().jump().publish(GameEvent::Jump),
                 _state: Jumping,
             }
         }
 
         pub fn knock_out(self) -> RedHatBoyState<Falling> {
             RedHatBoyState {
-                context: self.context.reset_frame().stop(),
+                context: self
+                    .context
+                    .reset_frame()
+                    .stop()
+                    .publish(GameEvent::KnockOut),
                 _state: Falling,
             }
         }
 
-        pub fn land_on(self, position: i16) -> Self {
+        pub fn land_on(self, position: f32) -> Self {
             RedHatBoyState {
                 context: self.context.set_on(position),
                 _state: self._state,
@@ -502,7 +652,7 @@ mod red_hat_boy_states {
 
         pub fn slide(self) -> RedHatBoyState<Sliding> {
             RedHatBoyState {
-                context: self.context.reset_frame(),
+                context: self.context.reset_frame().publish(GameEvent::Slide),
                 _state: Sliding,
             }
         }
@@ -524,12 +674,16 @@ mod red_hat_boy_states {
 
         pub fn knock_out(self) -> RedHatBoyState<Falling> {
             RedHatBoyState {
-                context: self.context.reset_frame().stop(),
+                context: self
+                    .context
+                    .reset_frame()
+                    .stop()
+                    .publish(GameEvent::KnockOut),
                 _state: Falling,
             }
         }
 
-        pub fn land_on(self, position: i16) -> Self {
+        pub fn land_on(self, position: f32) -> Self {
             RedHatBoyState {
                 context: self.context.set_on(position),
                 _state: self._state,
@@ -553,5 +707,9 @@ mod red_hat_boy_states {
         pub fn context(&self) -> &RedHatBoyContext {
             &self.context
         }
+
+        pub fn context_mut(&mut self) -> &mut RedHatBoyContext {
+            &mut self.context
+        }
     }
 }
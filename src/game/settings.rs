@@ -0,0 +1,297 @@
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    browser,
+    engine::{audio::AudioSettings, juice::JuiceSettings, ui::UiColors},
+};
+
+const SETTINGS_KEY: &str = "walk_the_dog.settings";
+// Where audio and effects settings were stored before they were combined
+const OLD_AUDIO_KEY: &str = "walk_the_dog.audio";
+const OLD_EFFECTS_KEY: &str = "walk_the_dog.accessibility";
+
+/// Okabe-Ito blues and orange, which stay distinct for every common colour vision deficiency.
+const COLOR_BLIND_COLORS: UiColors = UiColors {
+    button: "#0072b2",
+    button_hot: "#56b4e9",
+    button_active: "#004f7c",
+    focus: "#e69f00",
+    button_sheet: false,
+};
+
+/// Everything the player can configure. Fields missing from stored settings,
+/// such as ones added since they were saved, take their defaults.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub audio: AudioSettings,
+    pub controls: KeyBindings,
+    pub reduced_motion: bool,
+    pub effects: JuiceSettings,
+    pub show_fps: bool,
+    pub difficulty: Difficulty,
+    pub palette: Palette,
+    /// Log filter directives, such as `warn,engine::audio=debug`. The console's
+    /// `log` command changes them while the game runs.
+    pub log_filter: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            audio: AudioSettings::default(),
+            controls: KeyBindings::default(),
+            reduced_motion: false,
+            effects: JuiceSettings::default(),
+            show_fps: cfg!(debug_assertions),
+            difficulty: Difficulty::Normal,
+            palette: Palette::Standard,
+            log_filter: String::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// Falls back to settings carried over from older versions, then the
+    /// defaults, when nothing valid has been stored.
+    pub fn load() -> Self {
+        browser::load_json(SETTINGS_KEY)
+            .unwrap_or_else(|err| {
+                error!("Could not load settings {:#?}", err);
+                None
+            })
+            .unwrap_or_else(Settings::migrate)
+    }
+
+    /// Carries over the audio and effects settings older versions stored under
+    /// their own keys, removing those keys once they are saved here.
+    fn migrate() -> Self {
+        let mut settings = Settings::default();
+        let audio = load_old(OLD_AUDIO_KEY);
+        let effects = load_old(OLD_EFFECTS_KEY);
+        if audio.is_none() && effects.is_none() {
+            return settings;
+        }
+        settings.audio = audio.unwrap_or(settings.audio);
+        settings.effects = effects.unwrap_or(settings.effects);
+        match settings.save() {
+            Ok(()) => {
+                for key in [OLD_AUDIO_KEY, OLD_EFFECTS_KEY] {
+                    if let Err(err) = browser::remove_item(key) {
+                        error!("Could not remove old settings {:#?}", err);
+                    }
+                }
+                log!("Moved old settings to {}", SETTINGS_KEY);
+            }
+            Err(err) => error!("Could not save migrated settings {:#?}", err),
+        }
+        settings
+    }
+
+    pub fn save(&self) -> Result<()> {
+        browser::store_json(SETTINGS_KEY, self)
+    }
+
+    /// The effects to play, with shake and flashes off under reduced motion.
+    pub fn juice(&self) -> JuiceSettings {
+        if self.reduced_motion {
+            JuiceSettings {
+                screen_shake: false,
+                flash: false,
+                ..self.effects
+            }
+        } else {
+            self.effects
+        }
+    }
+}
+
+fn load_old<T: DeserializeOwned>(key: &str) -> Option<T> {
+    browser::load_json(key).unwrap_or_else(|err| {
+        error!("Could not load old settings {:#?}", err);
+        None
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Run,
+    Jump,
+    Slide,
+}
+
+impl Action {
+    pub const ALL: [Action; 3] = [Action::Run, Action::Jump, Action::Slide];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Run => "Run",
+            Action::Jump => "Jump",
+            Action::Slide => "Slide",
+        }
+    }
+}
+
+/// Key codes, as in `KeyboardEvent.code`, or gamepad button names.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub run: String,
+    pub jump: String,
+    pub slide: String,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            run: "ArrowRight".into(),
+            jump: "Space".into(),
+            slide: "ArrowDown".into(),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, action: Action) -> &str {
+        match action {
+            Action::Run => &self.run,
+            Action::Jump => &self.jump,
+            Action::Slide => &self.slide,
+        }
+    }
+
+    /// Binds `key` to `action`. An action already on `key` swaps to the key
+    /// `action` had, so no key does two things.
+    pub fn bind(&mut self, action: Action, key: &str) {
+        let previous = self.key(action).to_string();
+        if let Some(other) = Action::ALL
+            .into_iter()
+            .find(|other| *other != action && self.key(*other) == key)
+        {
+            self.set(other, &previous);
+        }
+        self.set(action, key);
+    }
+
+    fn set(&mut self, action: Action, key: &str) {
+        match action {
+            Action::Run => self.run = key.into(),
+            Action::Jump => self.jump = key.into(),
+            Action::Slide => self.slide = key.into(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    /// Extra space left before each new segment of obstacles. Hard keeps the
+    /// tightest spacing that still leaves room to land and jump again.
+    pub fn obstacle_gap(&self) -> i32 {
+        match self {
+            Difficulty::Easy => 300,
+            Difficulty::Normal => 120,
+            Difficulty::Hard => 20,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Palette {
+    Standard,
+    ColorBlind,
+}
+
+impl Palette {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Standard => "standard",
+            Palette::ColorBlind => "color-blind",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Palette::Standard => Palette::ColorBlind,
+            Palette::ColorBlind => Palette::Standard,
+        }
+    }
+
+    pub fn ui_colors(&self) -> UiColors {
+        match self {
+            Palette::Standard => UiColors::STANDARD,
+            Palette::ColorBlind => COLOR_BLIND_COLORS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reduced_motion_overrides_shake_and_flash() {
+        let mut settings = Settings::default();
+        settings.effects.flash = true;
+        settings.reduced_motion = true;
+
+        let juice = settings.juice();
+
+        assert!(!juice.screen_shake);
+        assert!(!juice.flash);
+        assert!(juice.hit_stop);
+    }
+
+    #[test]
+    fn actions_can_be_rebound() {
+        let mut controls = KeyBindings::default();
+
+        controls.bind(Action::Jump, "KeyW");
+
+        assert_eq!(controls.key(Action::Jump), "KeyW");
+        assert_eq!(controls.key(Action::Run), "ArrowRight");
+    }
+
+    #[test]
+    fn binding_a_key_in_use_swaps_the_keys() {
+        let mut controls = KeyBindings::default();
+
+        controls.bind(Action::Jump, "ArrowDown");
+
+        assert_eq!(controls.key(Action::Jump), "ArrowDown");
+        assert_eq!(controls.key(Action::Slide), "Space");
+        assert_eq!(controls.key(Action::Run), "ArrowRight");
+    }
+
+    #[test]
+    fn each_difficulty_leaves_a_clearly_different_gap() {
+        let gaps: Vec<i32> = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard]
+            .iter()
+            .map(Difficulty::obstacle_gap)
+            .collect();
+
+        assert!(gaps.windows(2).all(|pair| pair[0] - pair[1] >= 100));
+    }
+}
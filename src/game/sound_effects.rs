@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use rand::{thread_rng, Rng};
+use serde::Deserialize;
+
+use crate::{
+    browser,
+    engine::{
+        audio::{Audio, Sound},
+        error::EngineError,
+    },
+};
+
+use super::events::GameEvent;
+
+/// Where the event to sound mapping is kept, so sounds can be changed without
+/// code. Only jump has a sound so far; events left out of it play nothing.
+const SOUND_EFFECTS: &str = "assets/sounds/sound_effects.json";
+
+#[derive(Deserialize)]
+struct SoundEffect {
+    event: GameEvent,
+    resource: String,
+    volume: f32,
+    pitch_variation: f32,
+}
+
+pub struct SoundEffects {
+    audio: Audio,
+    sounds: HashMap<GameEvent, (SoundEffect, Sound)>,
+}
+
+impl SoundEffects {
+    /// Effects whose file cannot be loaded are left silent rather than failing the game.
+    pub async fn load(audio: Audio) -> Self {
+        let mut sounds = HashMap::new();
+        let effects = load_mapping().await.unwrap_or_else(|err| {
+            warn!("No sound effects: {:#?}", err);
+            vec![]
+        });
+        for effect in effects {
+            match audio.load_sound(&effect.resource).await {
+                Ok(sound) => {
+                    sounds.insert(effect.event, (effect, sound));
+                }
+                Err(err) => {
+                    warn!("No sound for {:?}: {:#?}", effect.event, err);
+                }
+            }
+        }
+        SoundEffects { audio, sounds }
+    }
+
+    pub fn play(&self, event: GameEvent) {
+        if let Some((effect, sound)) = self.sounds.get(&event) {
+            let rate = 1.0 + thread_rng().gen_range(-1.0..=1.0) * effect.pitch_variation;
+            let result = self.audio.play_sound(sound).and_then(|playback| {
+                playback.set_playback_rate(rate);
+                playback.set_volume(effect.volume)
+            });
+            if let Err(err) = result {
+                error!("Error playing sound for {:?} {:#?}", event, err);
+            }
+        }
+    }
+}
+
+async fn load_mapping() -> Result<Vec<SoundEffect>> {
+    Ok(
+        serde_wasm_bindgen::from_value(browser::fetch_json(SOUND_EFFECTS).await?)
+            .map_err(|err| EngineError::resource(SOUND_EFFECTS, err))?,
+    )
+}
@@ -1,24 +1,59 @@
 use std::rc::Rc;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use futures::channel::mpsc::UnboundedReceiver;
 
 use crate::{
-    browser,
-    engine::{self, audio::Audio, Game, Image, KeyState, Point, Rect, Renderer, SpriteSheet},
-    segment::stone_and_platform,
+    browser::{self, ui::Panel},
+    engine::{
+        self,
+        audio::Audio,
+        hot_reload::HotReload,
+        juice::Juice,
+        logging::{self, Filter},
+        profiler::Profiler,
+        ui::Ui,
+        Game, KeyState, Point, Rect, Renderer, SpriteSheet,
+    },
+    segment::{SegmentTable, SEGMENT_MANIFEST},
 };
 
-use super::{redhatboy::RedHatBoy, rightmost, Walk, HEIGHT, TIMELINE_MINIMUM, WIDTH};
+use super::{
+    audio_controls::AudioControls,
+    console::DevConsole,
+    editor::{Editor, EditorAction},
+    events::{GameEvent, GameEvents},
+    high_scores::HighScores,
+    hud::Hud,
+    index, load_background, load_foreground, load_image, load_optional_image,
+    menu::{Menu, MenuAction},
+    music::Music,
+    new_camera, new_rng,
+    particle_effects::ParticleEffects,
+    redhatboy::RedHatBoy,
+    rightmost,
+    settings::{KeyBindings, Settings},
+    sound_effects::SoundEffects,
+    Walk, HEIGHT, TIMELINE_MINIMUM, WIDTH,
+};
 
 pub struct WalkTheDog {
     machine: Option<WalkTheDogStateMachine>,
+    audio: Option<Audio>,
+    audio_controls: Option<AudioControls>,
+    console: DevConsole,
+    hot_reload: Option<HotReload>,
+    profiler: Profiler,
+    settings: Settings,
+    ui: Ui,
 }
 
 enum WalkTheDogStateMachine {
+    Title(WalkTheDogState<Title>),
     Ready(WalkTheDogState<Ready>),
     Walking(WalkTheDogState<Walking>),
     GameOver(WalkTheDogState<GameOver>),
+    Editing(WalkTheDogState<Editing>),
 }
 
 struct WalkTheDogState<T> {
@@ -26,14 +61,29 @@ struct WalkTheDogState<T> {
     _state: T,
 }
 
+struct Title {
+    menu: Menu,
+}
+
 struct Ready;
 
 struct Walking;
 
 struct GameOver {
+    _panels: Vec<Panel>,
     new_game_event: UnboundedReceiver<()>,
 }
 
+struct Editing {
+    editor: Editor,
+}
+
+enum TitleEndState {
+    Complete(WalkTheDogState<Ready>),
+    Edit(WalkTheDogState<Editing>),
+    Continue(WalkTheDogState<Title>),
+}
+
 enum ReadyEndState {
     Complete(WalkTheDogState<Walking>),
     Continue(WalkTheDogState<Ready>),
@@ -46,12 +96,44 @@ enum WalkingEndState {
 
 enum GameOverEndState {
     Complete(WalkTheDogState<Ready>),
+    Edit(WalkTheDogState<Editing>),
     Continue(WalkTheDogState<GameOver>),
 }
 
+enum EditingEndState {
+    TestPlay(WalkTheDogState<Ready>),
+    Exit(WalkTheDogState<Title>),
+    Continue(WalkTheDogState<Editing>),
+}
+
 impl WalkTheDog {
-    pub fn new() -> Self {
-        WalkTheDog { machine: None }
+    pub fn new(settings: Settings, profiler: Profiler) -> Self {
+        WalkTheDog {
+            machine: None,
+            audio: None,
+            audio_controls: None,
+            console: DevConsole::new(),
+            hot_reload: None,
+            profiler,
+            settings,
+            ui: Ui::new(),
+        }
+    }
+
+    /// Pushes changed settings to every subsystem that reads them, then stores them.
+    fn apply_settings(&mut self) {
+        if let Some(audio) = &self.audio {
+            if let Err(err) = audio.set_settings(self.settings.audio) {
+                error!("Could not apply audio settings {:#?}", err);
+            }
+        }
+        if let Some(machine) = &mut self.machine {
+            machine.walk_mut().apply_settings(&self.settings);
+        }
+        self.ui.set_colors(self.settings.palette.ui_colors());
+        if let Err(err) = self.settings.save() {
+            error!("Could not save settings {:#?}", err);
+        }
     }
 }
 
@@ -59,36 +141,38 @@ impl Game for WalkTheDog {
     async fn initialize(&self) -> anyhow::Result<Box<impl Game + 'static>> {
         match self.machine {
             None => {
+                let mut hot_reload = HotReload::new();
+
                 // Background
-                let background =
-                    engine::load_image("assets/original/freetileset/png/BG/BG.png").await?;
-                let background_width = background.width() as i16;
-                let backgrounds = [
-                    Image::new(background.clone(), Point { x: 0, y: 0 }),
-                    Image::new(
-                        background,
-                        Point {
-                            x: background_width,
-                            y: 0,
-                        },
-                    ),
-                ];
+                let background = load_background(&mut hot_reload).await?;
+                let foreground = load_foreground(&mut hot_reload).await;
 
                 // Red hat boy
-                let sprite_sheet = SpriteSheet::new(
-                    "assets/sprite_sheets/rhb_trimmed.json",
-                    "assets/sprite_sheets/rhb_trimmed.png",
-                )
-                .await?;
-                let audio = Audio::new()?;
-                let jump_sound = audio.load_sound("assets/sounds/SFX_Jump_23.mp3").await?;
-                let background_music = audio
-                    .load_sound("assets/sounds/background_song.mp3")
-                    .await?;
-                if let Err(err) = audio.play_loop(&background_music) {
-                    error!("Error starting the audio loop {:#?}", err);
+                let sprite_sheet = Rc::new(
+                    SpriteSheet::new(
+                        "assets/sprite_sheets/rhb_trimmed.json",
+                        "assets/sprite_sheets/rhb_trimmed.png",
+                    )
+                    .await?,
+                );
+                hot_reload.watch_sprite_sheet(&sprite_sheet);
+                let settings = self.settings.clone();
+                let audio = Audio::new(settings.audio)?;
+                let events = GameEvents::new();
+                let audio_scope = self.profiler.scope("load audio");
+                let sound_effects = SoundEffects::load(audio.clone()).await;
+                let mut music = Music::load(audio.clone()).await?;
+                drop(audio_scope);
+                music.start();
+                let audio_controls = AudioControls::new(audio.clone(), events.clone());
+                let mut ui = Ui::new();
+                ui.set_colors(settings.palette.ui_colors());
+                if let Some(button_image) =
+                    load_optional_image("assets/ui/Button.svg", &mut hot_reload).await
+                {
+                    ui.set_button_image(button_image);
                 }
-                let boy = RedHatBoy::new(sprite_sheet, audio, jump_sound);
+                let boy = RedHatBoy::new(sprite_sheet, events.clone());
 
                 // Platform sprite sheet
                 let obstacle_sheet = Rc::new(
@@ -98,39 +182,93 @@ impl Game for WalkTheDog {
                     )
                     .await?,
                 );
+                hot_reload.watch_sprite_sheet(&obstacle_sheet);
 
                 // Stone
-                let stone =
-                    engine::load_image("assets/original/freetileset/png/Object/Stone.png").await?;
+                let stone = load_image(
+                    "assets/original/freetileset/png/Object/Stone.png",
+                    &mut hot_reload,
+                )
+                .await?;
 
-                // Starting obstacles
-                let starting_obstacles =
-                    stone_and_platform(stone.clone(), obstacle_sheet.clone(), 0);
-                let timeline = rightmost(&starting_obstacles);
+                // Segments
+                let segments = Rc::new(SegmentTable::load(SEGMENT_MANIFEST).await?);
+                hot_reload.watch_data(segments.clone());
+                let starting_obstacles = segments.starting().build(&stone, &obstacle_sheet, 0.0);
+                let timeline = rightmost(&starting_obstacles) as f32;
+                let (rng, seed) = new_rng(None);
 
                 // State machine
                 let machine = WalkTheDogStateMachine::new(Walk {
-                    backgrounds,
+                    background,
+                    camera: new_camera(&boy),
                     boy,
+                    broad_phase: index(&starting_obstacles),
+                    difficulty: settings.difficulty,
+                    events,
+                    foreground,
+                    hud: Hud::new(),
+                    juice: Juice::new(settings.juice()),
+                    music,
                     obstacles: starting_obstacles,
                     obstacle_sheet,
+                    particles: ParticleEffects::new(),
+                    profiler: self.profiler.clone(),
+                    rng,
+                    seed,
+                    segments,
+                    sound_effects,
                     stone,
+                    testing: None,
                     timeline,
                 });
 
                 Ok(Box::new(WalkTheDog {
                     machine: Some(machine),
+                    audio: Some(audio),
+                    audio_controls: Some(audio_controls),
+                    console: DevConsole::new(),
+                    hot_reload: Some(hot_reload),
+                    profiler: self.profiler.clone(),
+                    settings,
+                    ui,
                 }))
             }
             Some(_) => Err(anyhow!("Error: Game is already initialized!")),
         }
     }
 
-    fn update(&mut self, keystate: &engine::KeyState) {
-        if let Some(machine) = self.machine.take() {
-            self.machine.replace(machine.update(keystate));
+    fn update(&mut self, keystate: &engine::KeyState) -> Result<()> {
+        let machine = self
+            .machine
+            .take()
+            .ok_or_else(|| anyhow!("Error: Game is not initialized!"))?;
+        // Menus take the arrow keys and gamepad, which otherwise control the boy
+        self.ui.set_keyboard_navigation(machine.is_in_menu());
+        self.ui.begin(keystate);
+        let previous_settings = self.settings.clone();
+        let mut machine = machine.update(keystate, &mut self.ui, &mut self.settings)?;
+        if let Err(err) = self
+            .console
+            .update(keystate, machine.walk_mut(), &mut self.settings)
+        {
+            error!("Could not update the developer console {:#?}", err);
+        }
+        if let Some(hot_reload) = &mut self.hot_reload {
+            hot_reload.update(keystate);
+        }
+        self.machine.replace(machine);
+        if let Some(audio_controls) = &mut self.audio_controls {
+            audio_controls.update(keystate, &mut self.ui, &mut self.settings.audio);
+        }
+        if self.settings.log_filter != previous_settings.log_filter {
+            logging::set_filter(Filter::parse(&self.settings.log_filter));
+            log!("Logging with filter '{}'", self.settings.log_filter);
         }
-        assert!(self.machine.is_some());
+        if self.settings != previous_settings {
+            self.apply_settings();
+        }
+        Ok(())
     }
 
     fn draw(&self, renderer: &engine::Renderer) -> Result<()> {
@@ -139,33 +277,114 @@ impl Game for WalkTheDog {
         if let Some(machine) = &self.machine {
             machine.draw(renderer)?;
         }
+        self.ui.draw(renderer)?;
 
         Ok(())
     }
+
+    fn show_frame_rate(&self) -> bool {
+        self.settings.show_fps
+    }
+
+    fn debug_info(&self) -> Vec<String> {
+        self.machine
+            .as_ref()
+            .map(|machine| machine.walk().debug_info())
+            .unwrap_or_default()
+    }
 }
 
 impl WalkTheDogStateMachine {
     fn new(walk: Walk) -> WalkTheDogStateMachine {
-        WalkTheDogStateMachine::Ready(WalkTheDogState::new(walk))
+        WalkTheDogStateMachine::Title(WalkTheDogState::<Title>::new(walk))
     }
 
-    fn update(self, keystate: &KeyState) -> Self {
-        match self {
-            WalkTheDogStateMachine::Ready(state) => state.update(keystate).into(),
-            WalkTheDogStateMachine::Walking(state) => state.update(keystate).into(),
+    fn update(self, keystate: &KeyState, ui: &mut Ui, settings: &mut Settings) -> Result<Self> {
+        let controls = &settings.controls;
+        let mut machine: WalkTheDogStateMachine = match self {
+            WalkTheDogStateMachine::Title(state) => state.update(keystate, ui, settings).into(),
+            WalkTheDogStateMachine::Ready(state) => state.update(keystate, controls).into(),
+            WalkTheDogStateMachine::Walking(state) => state.update(keystate, controls)?.into(),
             WalkTheDogStateMachine::GameOver(state) => state.update().into(),
+            WalkTheDogStateMachine::Editing(state) => state.update(keystate, ui).into(),
+        };
+        let walk = machine.walk_mut();
+        let _scope = walk.profiler.scope("effects");
+        walk.process_events();
+        walk.update_effects();
+        Ok(machine)
+    }
+
+    fn is_in_menu(&self) -> bool {
+        match self {
+            WalkTheDogStateMachine::Title(state) => !state._state.menu.is_rebinding(),
+            _ => false,
+        }
+    }
+
+    fn walk(&self) -> &Walk {
+        match self {
+            WalkTheDogStateMachine::Title(state) => &state.walk,
+            WalkTheDogStateMachine::Ready(state) => &state.walk,
+            WalkTheDogStateMachine::Walking(state) => &state.walk,
+            WalkTheDogStateMachine::GameOver(state) => &state.walk,
+            WalkTheDogStateMachine::Editing(state) => &state.walk,
+        }
+    }
+
+    fn walk_mut(&mut self) -> &mut Walk {
+        match self {
+            WalkTheDogStateMachine::Title(state) => &mut state.walk,
+            WalkTheDogStateMachine::Ready(state) => &mut state.walk,
+            WalkTheDogStateMachine::Walking(state) => &mut state.walk,
+            WalkTheDogStateMachine::GameOver(state) => &mut state.walk,
+            WalkTheDogStateMachine::Editing(state) => &mut state.walk,
         }
     }
 
     fn draw(&self, renderer: &Renderer) -> Result<()> {
         match self {
+            WalkTheDogStateMachine::Title(state) => state.draw(renderer),
             WalkTheDogStateMachine::Ready(state) => state.draw(renderer),
             WalkTheDogStateMachine::Walking(state) => state.draw(renderer),
             WalkTheDogStateMachine::GameOver(state) => state.draw(renderer),
+            WalkTheDogStateMachine::Editing(state) => state.draw(renderer),
         }
     }
 }
 
+impl WalkTheDogState<Title> {
+    fn new(walk: Walk) -> WalkTheDogState<Title> {
+        let menu = Menu::new(walk.events.clone());
+        WalkTheDogState {
+            walk,
+            _state: Title { menu },
+        }
+    }
+
+    fn update(
+        mut self,
+        keystate: &KeyState,
+        ui: &mut Ui,
+        settings: &mut Settings,
+    ) -> TitleEndState {
+        self.walk.boy.update();
+        match self._state.menu.update(keystate, ui, settings) {
+            Some(MenuAction::Play) => {
+                TitleEndState::Complete(WalkTheDogState::<Ready>::new(self.walk))
+            }
+            Some(MenuAction::Edit) => {
+                TitleEndState::Edit(WalkTheDogState::<Editing>::new(self.walk))
+            }
+            None => TitleEndState::Continue(self),
+        }
+    }
+
+    fn draw(&self, renderer: &Renderer) -> Result<()> {
+        self.walk.draw(renderer)
+    }
+}
+
 impl WalkTheDogState<Ready> {
     fn new(walk: Walk) -> WalkTheDogState<Ready> {
         WalkTheDogState {
@@ -174,9 +393,9 @@ impl WalkTheDogState<Ready> {
         }
     }
 
-    fn update(mut self, keystate: &KeyState) -> ReadyEndState {
+    fn update(mut self, keystate: &KeyState, controls: &KeyBindings) -> ReadyEndState {
         self.walk.boy.update();
-        if keystate.is_pressed("ArrowRight") {
+        if keystate.is_pressed(&controls.run) {
             ReadyEndState::Complete(self.start_running())
         } else {
             ReadyEndState::Continue(self)
@@ -189,6 +408,7 @@ impl WalkTheDogState<Ready> {
 
     fn start_running(mut self) -> WalkTheDogState<Walking> {
         self.run();
+        self.walk.music.start();
         WalkTheDogState {
             walk: self.walk,
             _state: Walking,
@@ -201,71 +421,116 @@ impl WalkTheDogState<Ready> {
 }
 
 impl WalkTheDogState<Walking> {
-    fn update(mut self, keystate: &KeyState) -> WalkingEndState {
-        if keystate.is_pressed("Space") {
+    fn update(mut self, keystate: &KeyState, controls: &KeyBindings) -> Result<WalkingEndState> {
+        if self.walk.juice.consume_hit_stop() {
+            return Ok(WalkingEndState::Continue(self));
+        }
+        if keystate.is_pressed(&controls.jump) {
             self.walk.boy.jump();
         }
-        if keystate.is_pressed("ArrowDown") {
+        if keystate.is_pressed(&controls.slide) {
             self.walk.boy.slide();
         }
+        let boy_scope = self.walk.profiler.scope("boy");
         self.walk.boy.update();
-
-        let walking_speed = self.walk.velocity();
-
-        // Backgrounds
-        let [first_background, second_background] = &mut self.walk.backgrounds;
-        first_background.move_horizontally(walking_speed);
-        second_background.move_horizontally(walking_speed);
-        if first_background.right() < 0 {
-            first_background.set_x(second_background.right());
-        }
-        if second_background.right() < 0 {
-            second_background.set_x(first_background.right());
-        }
+        self.walk.follow_boy();
+        self.walk.hud.add_distance(self.walk.boy.velocity().x);
+        drop(boy_scope);
 
         // Obstacles
-        self.walk.obstacles.retain(|obstacle| obstacle.right() > 0);
-        self.walk.obstacles.iter_mut().for_each(|obstacle| {
-            obstacle.move_horizontally(walking_speed);
-            obstacle.check_intersection(&mut self.walk.boy);
-        });
+        let collisions_scope = self.walk.profiler.scope("collisions");
+        self.walk.remove_passed_obstacles();
+        let swept_box = self.walk.boy.swept_box();
+        for index in self.walk.broad_phase.query(&swept_box) {
+            self.walk.obstacles[index].check_intersection(&mut self.walk.boy);
+        }
+        drop(collisions_scope);
 
         // Timeline
         if self.walk.timeline < TIMELINE_MINIMUM {
             self.walk.generate_next_segment();
         } else {
-            self.walk.timeline += walking_speed;
+            self.walk.timeline -= self.walk.boy.velocity().x;
         }
 
-        if self.walk.knocked_out() {
-            WalkingEndState::Complete(self.end_game())
+        Ok(if self.walk.knocked_out() {
+            WalkingEndState::Complete(self.end_game()?)
         } else {
             WalkingEndState::Continue(self)
-        }
+        })
     }
 
     fn draw(&self, renderer: &Renderer) -> Result<()> {
         self.walk.draw(renderer)
     }
 
-    fn end_game(self) -> WalkTheDogState<GameOver> {
-        let receiver = browser::draw_ui("<button id=\"new_game\">New game</button>")
-            .and_then(|_unit| browser::find_html_element_by_id("new_game"))
-            .map(engine::add_click_handler)
-            .unwrap();
-        WalkTheDogState {
+    fn end_game(mut self) -> Result<WalkTheDogState<GameOver>> {
+        self.walk.music.stop();
+        self.walk.events.publish(GameEvent::GameOver);
+        let (panels, new_game_event) = self
+            .show_game_over()
+            .context("Could not show the game over screen")?;
+        Ok(WalkTheDogState {
             walk: self.walk,
             _state: GameOver {
-                new_game_event: receiver,
+                _panels: panels,
+                new_game_event,
             },
+        })
+    }
+
+    fn show_game_over(&self) -> Result<(Vec<Panel>, UnboundedReceiver<()>)> {
+        let score = self.walk.hud.score();
+        let mut high_scores = HighScores::load();
+        // Test plays from the editor aren't real runs
+        let rank = if self.walk.testing.is_some() {
+            None
+        } else {
+            high_scores.add(score)
+        };
+        if rank.is_some() {
+            if let Err(err) = high_scores.save() {
+                error!("Could not save high scores {:#?}", err);
+            }
         }
+
+        let mut game_over = Panel::new("game_over")?;
+        game_over.heading("Game over")?;
+        game_over.text(&format!("Score: {}", score))?;
+        let new_game_event = game_over.button(if self.walk.testing.is_some() {
+            "Back to editor"
+        } else {
+            "New game"
+        })?;
+
+        let mut scores = Panel::new("high_scores")?;
+        scores.heading(if rank.is_some() {
+            "New high score!"
+        } else {
+            "High scores"
+        })?;
+        scores.list(
+            &high_scores
+                .scores()
+                .iter()
+                .map(|score| score.to_string())
+                .collect::<Vec<_>>(),
+        )?;
+
+        Ok((vec![game_over, scores], new_game_event))
     }
 }
 
 impl WalkTheDogState<GameOver> {
     fn update(mut self) -> GameOverEndState {
         if self._state.new_game_pressed() {
-            GameOverEndState::Complete(self.new_game())
+            self.walk.events.publish(GameEvent::ButtonClick);
+            // Test plays of an edited segment go back to the editor
+            if self.walk.testing.is_some() {
+                GameOverEndState::Edit(self.back_to_editor())
+            } else {
+                GameOverEndState::Complete(self.new_game())
+            }
         } else {
             GameOverEndState::Continue(self)
         }
@@ -276,14 +541,57 @@ impl WalkTheDogState<GameOver> {
     }
 
     fn new_game(self) -> WalkTheDogState<Ready> {
-        if let Err(err) = browser::hide_ui() {
-            error!("Error hiding the browser {:#?}", err);
+        // Dropping the game over state takes its panels off the page
+        let WalkTheDogState { walk, _state } = self;
+        drop(_state);
+        if let Err(err) = browser::focus_canvas() {
+            error!("Could not give focus back to the canvas {:#?}", err);
         }
+        let mut walk = Walk::reset(walk);
+        walk.music.start();
         WalkTheDogState {
-            walk: Walk::reset(self.walk),
+            walk,
             _state: Ready,
         }
     }
+
+    fn back_to_editor(mut self) -> WalkTheDogState<Editing> {
+        self.walk.testing = None;
+        WalkTheDogState::<Editing>::new(self.new_game().walk)
+    }
+}
+
+impl WalkTheDogState<Editing> {
+    fn new(walk: Walk) -> WalkTheDogState<Editing> {
+        let editor = Editor::load(
+            walk.segments.starting(),
+            walk.stone.clone(),
+            walk.obstacle_sheet.clone(),
+            walk.events.clone(),
+        );
+        WalkTheDogState {
+            walk,
+            _state: Editing { editor },
+        }
+    }
+
+    fn update(mut self, keystate: &KeyState, ui: &mut Ui) -> EditingEndState {
+        match self._state.editor.update(keystate, ui) {
+            Some(EditorAction::TestPlay(segment)) => {
+                let mut walk = self.walk;
+                walk.testing = Some(segment);
+                EditingEndState::TestPlay(WalkTheDogState::<Ready>::new(Walk::reset(walk)))
+            }
+            Some(EditorAction::Exit) => {
+                EditingEndState::Exit(WalkTheDogState::<Title>::new(self.walk))
+            }
+            None => EditingEndState::Continue(self),
+        }
+    }
+
+    fn draw(&self, renderer: &Renderer) -> Result<()> {
+        self._state.editor.draw(renderer, &self.walk.background)
+    }
 }
 
 impl GameOver {
@@ -292,6 +600,12 @@ impl GameOver {
     }
 }
 
+impl From<WalkTheDogState<Title>> for WalkTheDogStateMachine {
+    fn from(state: WalkTheDogState<Title>) -> Self {
+        WalkTheDogStateMachine::Title(state)
+    }
+}
+
 impl From<WalkTheDogState<Ready>> for WalkTheDogStateMachine {
     fn from(state: WalkTheDogState<Ready>) -> Self {
         WalkTheDogStateMachine::Ready(state)
@@ -310,6 +624,22 @@ impl From<WalkTheDogState<GameOver>> for WalkTheDogStateMachine {
     }
 }
 
+impl From<WalkTheDogState<Editing>> for WalkTheDogStateMachine {
+    fn from(state: WalkTheDogState<Editing>) -> Self {
+        WalkTheDogStateMachine::Editing(state)
+    }
+}
+
+impl From<TitleEndState> for WalkTheDogStateMachine {
+    fn from(end_state: TitleEndState) -> Self {
+        match end_state {
+            TitleEndState::Complete(ready_state) => ready_state.into(),
+            TitleEndState::Edit(editing_state) => editing_state.into(),
+            TitleEndState::Continue(title_state) => title_state.into(),
+        }
+    }
+}
+
 impl From<ReadyEndState> for WalkTheDogStateMachine {
     fn from(end_state: ReadyEndState) -> Self {
         match end_state {
@@ -332,7 +662,18 @@ impl From<GameOverEndState> for WalkTheDogStateMachine {
     fn from(end_state: GameOverEndState) -> Self {
         match end_state {
             GameOverEndState::Complete(ready_state) => ready_state.into(),
+            GameOverEndState::Edit(editing_state) => editing_state.into(),
             GameOverEndState::Continue(game_over_state) => game_over_state.into(),
         }
     }
 }
+
+impl From<EditingEndState> for WalkTheDogStateMachine {
+    fn from(end_state: EditingEndState) -> Self {
+        match end_state {
+            EditingEndState::TestPlay(ready_state) => ready_state.into(),
+            EditingEndState::Exit(title_state) => title_state.into(),
+            EditingEndState::Continue(editing_state) => editing_state.into(),
+        }
+    }
+}
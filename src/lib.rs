@@ -5,21 +5,52 @@ mod game;
 mod segment;
 mod sound;
 
-use engine::GameLoop;
-use game::WalkTheDog;
+use engine::{console, error, logging, profiler::Profiler, GameLoop};
+use game::{Settings, WalkTheDog};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen(start)]
 pub fn main_js() -> Result<(), JsValue> {
-    console_error_panic_hook::set_once();
+    std::panic::set_hook(Box::new(|info| {
+        console_error_panic_hook::hook(info);
+        error::show_panic_screen(info);
+    }));
 
     browser::spawn_local(async move {
-        let game = WalkTheDog::new();
+        let settings = Settings::load();
+        logging::configure(&settings.log_filter);
+        let profiler = Profiler::new();
+        let game = WalkTheDog::new(settings, profiler.clone());
 
-        GameLoop::start(game)
-            .await
-            .expect("Could not start game loop.");
+        if let Err(err) = GameLoop::start(game, profiler).await {
+            error::show_error_screen(&err.context("Could not start game loop"));
+        }
     });
 
     Ok(())
 }
+
+/// Runs a developer console command, such as `set gravity 0.5`, on the next
+/// update, resolving to its output. Only debug builds have the console.
+#[wasm_bindgen]
+pub async fn run_command(line: String) -> Result<String, JsValue> {
+    if !cfg!(debug_assertions) {
+        return Err(JsValue::from_str(
+            "The developer console is only in debug builds",
+        ));
+    }
+    console::submit(&line)
+        .await
+        .map_err(|_| JsValue::from_str("The game stopped before running the command"))?
+        .map_err(|err| JsValue::from_str(&err))
+}
+
+/// Every developer console command with its usage, in debug builds.
+#[wasm_bindgen]
+pub fn commands() -> Vec<String> {
+    if cfg!(debug_assertions) {
+        game::console_help()
+    } else {
+        vec![]
+    }
+}
@@ -1,78 +1,324 @@
-use std::rc::Rc;
+use std::{cell::RefCell, rc::Rc};
 
+use anyhow::{anyhow, Result};
+use futures::future::LocalBoxFuture;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 use web_sys::HtmlImageElement;
 
 use crate::{
-    engine::{Image, Point, Rect, SpriteSheet},
-    game::{Barrier, Obstacle, Platform},
+    browser,
+    engine::{error::EngineError, hot_reload::Reload, Image, Point, Rect, SpriteSheet, Vector},
+    game::{Barrier, Collectible, Obstacle, Platform},
 };
 
-const FIRST_PLATFORM: i16 = 370;
-const LOW_PLATFORM: i16 = 420;
-const HIGH_PLATFORM: i16 = 375;
-const STONE_ON_GROUND: i16 = 546;
-
-const FLOATING_PLATFORM_BOUNDING_BOXES: [Rect; 3] = [
-    Rect::new_from_x_y(0, 0, 60, 54),
-    Rect::new_from_x_y(60, 0, 384 - (60 * 2), 93),
-    Rect::new_from_x_y(384 - 60, 0, 60, 54),
-];
-
-const FLOATING_PLATFORM_SPRITES: [&str; 3] = ["13.png", "14.png", "15.png"];
-
-pub fn stone_and_platform(
-    stone: HtmlImageElement,
-    sprite_sheet: Rc<SpriteSheet>,
-    offset_x: i16,
-) -> Vec<Box<dyn Obstacle>> {
-    const INITIAL_STONE_OFFSET: i16 = 150;
-
-    vec![
-        Box::new(Barrier::new(Image::new(
-            stone,
-            Point {
-                x: offset_x + INITIAL_STONE_OFFSET,
-                y: STONE_ON_GROUND,
-            },
-        ))),
-        Box::new(create_floating_platform(
-            sprite_sheet,
-            Point {
-                x: offset_x + FIRST_PLATFORM,
-                y: LOW_PLATFORM,
-            },
-        )),
-    ]
+/// Lists the segment files, as paths from the site root. Segments exported from
+/// the editor join the game by being added here.
+pub const SEGMENT_MANIFEST: &str = "assets/segments/segments.json";
+
+pub const FLOATING_PLATFORM_SPRITES: [&str; 3] = ["13.png", "14.png", "15.png"];
+
+/// One obstacle of a segment, positioned from the segment's left edge.
+/// Bounding boxes are relative to the obstacle's position.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ObstacleData {
+    Barrier {
+        position: Point,
+        /// The whole stone image when missing.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bounding_box: Option<Rect>,
+    },
+    Platform {
+        position: Point,
+        /// Tiles from the tiles sprite sheet, drawn left to right.
+        sprites: Vec<String>,
+        bounding_boxes: Vec<Rect>,
+    },
+    Collectible {
+        position: Point,
+        /// A tile from the tiles sprite sheet.
+        sprite: String,
+        bounding_box: Rect,
+    },
+}
+
+impl ObstacleData {
+    pub fn position(&self) -> Point {
+        match self {
+            ObstacleData::Barrier { position, .. }
+            | ObstacleData::Platform { position, .. }
+            | ObstacleData::Collectible { position, .. } => *position,
+        }
+    }
+
+    pub fn set_position(&mut self, new_position: Point) {
+        match self {
+            ObstacleData::Barrier { position, .. }
+            | ObstacleData::Platform { position, .. }
+            | ObstacleData::Collectible { position, .. } => *position = new_position,
+        }
+    }
+
+    /// The bounding boxes relative to the position. Barriers without their
+    /// own box use `stone_box`, the whole stone image.
+    pub fn bounding_boxes(&self, stone_box: Rect) -> Vec<Rect> {
+        match self {
+            ObstacleData::Barrier { bounding_box, .. } => vec![bounding_box.unwrap_or(stone_box)],
+            ObstacleData::Platform { bounding_boxes, .. } => bounding_boxes.clone(),
+            ObstacleData::Collectible { bounding_box, .. } => vec![*bounding_box],
+        }
+    }
+
+    /// Barriers and collectibles have a single bounding box, whatever the `index`.
+    pub fn set_bounding_box(&mut self, index: usize, new_box: Rect) {
+        match self {
+            ObstacleData::Barrier { bounding_box, .. } => *bounding_box = Some(new_box),
+            ObstacleData::Collectible { bounding_box, .. } => *bounding_box = new_box,
+            ObstacleData::Platform { bounding_boxes, .. } => {
+                if let Some(bounding_box) = bounding_boxes.get_mut(index) {
+                    *bounding_box = new_box;
+                }
+            }
+        }
+    }
+
+    fn build(
+        &self,
+        stone: &HtmlImageElement,
+        sprite_sheet: &Rc<SpriteSheet>,
+        offset_x: f32,
+    ) -> Box<dyn Obstacle> {
+        let position = Vector::from(self.position()) + Vector::new(offset_x, 0.0);
+        match self {
+            ObstacleData::Barrier { bounding_box, .. } => {
+                let image = Image::new(stone.clone(), position);
+                Box::new(match bounding_box {
+                    Some(bounding_box) => Barrier::with_bounding_box(image, *bounding_box),
+                    None => Barrier::new(image),
+                })
+            }
+            ObstacleData::Platform {
+                sprites,
+                bounding_boxes,
+                ..
+            } => {
+                let sprites: Vec<&str> = sprites.iter().map(String::as_str).collect();
+                Box::new(Platform::new(
+                    bounding_boxes,
+                    position,
+                    sprite_sheet.clone(),
+                    &sprites,
+                ))
+            }
+            ObstacleData::Collectible {
+                sprite,
+                bounding_box,
+                ..
+            } => Box::new(Collectible::new(
+                *bounding_box,
+                position,
+                sprite_sheet.clone(),
+                sprite,
+            )),
+        }
+    }
+}
+
+/// A stretch of obstacles, as written by the segment editor.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SegmentData {
+    pub name: String,
+    pub obstacles: Vec<ObstacleData>,
 }
 
-pub fn platform_and_stone(
-    stone: HtmlImageElement,
-    sprite_sheet: Rc<SpriteSheet>,
-    offset_x: i16,
-) -> Vec<Box<dyn Obstacle>> {
-    vec![
-        Box::new(create_floating_platform(
-            sprite_sheet,
-            Point {
-                x: offset_x + 150,
-                y: HIGH_PLATFORM,
-            },
-        )),
-        Box::new(Barrier::new(Image::new(
-            stone,
-            Point {
-                x: offset_x + 370,
-                y: STONE_ON_GROUND,
-            },
-        ))),
-    ]
+impl SegmentData {
+    /// Reads a segment in the format the editor exports.
+    pub async fn load(resource: &str) -> Result<Self> {
+        Ok(
+            serde_wasm_bindgen::from_value(browser::fetch_json(resource).await?)
+                .map_err(|err| EngineError::resource(resource, err))?,
+        )
+    }
+
+    pub fn build(
+        &self,
+        stone: &HtmlImageElement,
+        sprite_sheet: &Rc<SpriteSheet>,
+        offset_x: f32,
+    ) -> Vec<Box<dyn Obstacle>> {
+        self.obstacles
+            .iter()
+            .map(|obstacle| obstacle.build(stone, sprite_sheet, offset_x))
+            .collect()
+    }
+}
+
+/// Every segment the game generates from, read from the JSON files named in a
+/// manifest. Runs start with the first one. The table can be swapped for fresh
+/// copies of the files while the game runs.
+pub struct SegmentTable {
+    manifest: String,
+    files: RefCell<Vec<String>>,
+    segments: RefCell<Vec<SegmentData>>,
+}
+
+impl SegmentTable {
+    pub async fn load(manifest: &str) -> Result<Self> {
+        let (files, segments) =
+            load_segments(manifest, |resource| Ok(resource.to_string())).await?;
+        Ok(SegmentTable {
+            manifest: manifest.to_string(),
+            files: RefCell::new(files),
+            segments: RefCell::new(segments),
+        })
+    }
+
+    pub fn starting(&self) -> SegmentData {
+        self.segments.borrow()[0].clone()
+    }
+
+    pub fn choose(&self, rng: &mut impl Rng) -> SegmentData {
+        let segments = self.segments.borrow();
+        segments[rng.gen_range(0..segments.len())].clone()
+    }
+
+    pub fn find(&self, name: &str) -> Option<SegmentData> {
+        self.segments
+            .borrow()
+            .iter()
+            .find(|segment| segment.name == name)
+            .cloned()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.segments
+            .borrow()
+            .iter()
+            .map(|segment| segment.name.clone())
+            .collect()
+    }
 }
 
-fn create_floating_platform(sprite_sheet: Rc<SpriteSheet>, position: Point) -> Platform {
-    Platform::new(
-        &FLOATING_PLATFORM_BOUNDING_BOXES,
+impl Reload for SegmentTable {
+    /// The manifest and every segment file it lists.
+    fn resources(&self) -> Vec<String> {
+        let mut resources = vec![self.manifest.clone()];
+        resources.extend(self.files.borrow().iter().cloned());
+        resources
+    }
+
+    /// Fetches the manifest and segments again, bypassing the browser cache.
+    fn reload(&self) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let (files, segments) = load_segments(&self.manifest, browser::cache_busted).await?;
+            *self.files.borrow_mut() = files;
+            *self.segments.borrow_mut() = segments;
+            Ok(())
+        })
+    }
+}
+
+/// Reads the files `manifest` lists, fetching each from the URL `locate` gives.
+async fn load_segments(
+    manifest: &str,
+    locate: fn(&str) -> Result<String>,
+) -> Result<(Vec<String>, Vec<SegmentData>)> {
+    let files: Vec<String> =
+        serde_wasm_bindgen::from_value(browser::fetch_json(&locate(manifest)?).await?)
+            .map_err(|err| EngineError::resource(manifest, err))?;
+    let mut segments = vec![];
+    for file in &files {
+        segments.push(SegmentData::load(&locate(file)?).await?);
+    }
+    if segments.is_empty() {
+        return Err(anyhow!("{} lists no segments", manifest));
+    }
+    Ok((files, segments))
+}
+
+pub fn stone(position: Point) -> ObstacleData {
+    ObstacleData::Barrier {
+        position,
+        bounding_box: None,
+    }
+}
+
+/// A platform of `sprites`, with one bounding box per tile.
+pub fn platform(position: Point, sprites: &[String], sprite_sheet: &SpriteSheet) -> ObstacleData {
+    let mut x = 0;
+    let bounding_boxes = sprites
+        .iter()
+        .filter_map(|sprite| sprite_sheet.cell(sprite))
+        .map(|cell| {
+            let frame = cell.frame();
+            let bounding_box = Rect::new_from_x_y(x, 0, frame.width, frame.height);
+            x += frame.width;
+            bounding_box
+        })
+        .collect();
+    ObstacleData::Platform {
+        position,
+        sprites: sprites.to_vec(),
+        bounding_boxes,
+    }
+}
+
+/// A collectible `sprite`, collected anywhere over the tile.
+pub fn collectible(position: Point, sprite: &str, sprite_sheet: &SpriteSheet) -> ObstacleData {
+    let bounding_box = sprite_sheet
+        .cell(sprite)
+        .map(|cell| Rect::new_from_x_y(0, 0, cell.frame().width, cell.frame().height))
+        .unwrap_or_default();
+    ObstacleData::Collectible {
         position,
-        sprite_sheet,
-        &FLOATING_PLATFORM_SPRITES,
-    )
+        sprite: sprite.to_string(),
+        bounding_box,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    async fn exported_segments_load_unchanged() {
+        let segment = SegmentData {
+            name: "round_trip".to_string(),
+            obstacles: vec![
+                stone(Point { x: 150, y: 546 }),
+                ObstacleData::Barrier {
+                    position: Point { x: 300, y: 546 },
+                    bounding_box: Some(Rect::new_from_x_y(10, 10, 70, 44)),
+                },
+                ObstacleData::Platform {
+                    position: Point { x: 370, y: 420 },
+                    sprites: FLOATING_PLATFORM_SPRITES
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                    bounding_boxes: vec![Rect::new_from_x_y(0, 0, 384, 93)],
+                },
+                ObstacleData::Collectible {
+                    position: Point { x: 500, y: 300 },
+                    sprite: "16.png".to_string(),
+                    bounding_box: Rect::new_from_x_y(0, 0, 64, 64),
+                },
+            ],
+        };
+
+        // As the editor exports it, then as the game loads it from assets
+        let exported = browser::to_json(&segment).unwrap();
+        let resource = format!(
+            "data:application/json,{}",
+            String::from(js_sys::encode_uri_component(&exported))
+        );
+        let loaded = SegmentData::load(&resource).await.unwrap();
+
+        assert_eq!(loaded, segment);
+    }
 }
@@ -1,34 +1,51 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use js_sys::ArrayBuffer;
-use wasm_bindgen::JsCast;
+use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{AudioBuffer, AudioBufferSourceNode, AudioContext, AudioDestinationNode, AudioNode};
+use web_sys::{
+    AudioBuffer, AudioBufferSourceNode, AudioContext, AudioNode, AudioScheduledSourceNode, GainNode,
+};
+
+use crate::engine::error::EngineError;
 
 pub fn create_audio_context() -> Result<AudioContext> {
-    AudioContext::new().map_err(|err| anyhow!("Could not create audio context {:#?}", err))
+    Ok(AudioContext::new().map_err(|err| EngineError::browser("create audio context", err))?)
 }
 
 fn create_buffer_source(ctx: &AudioContext) -> Result<AudioBufferSourceNode> {
-    ctx.create_buffer_source()
-        .map_err(|err| anyhow!("Could not create buffer source {:#?}", err))
+    Ok(ctx
+        .create_buffer_source()
+        .map_err(|err| EngineError::browser("create buffer source", err))?)
 }
 
-fn connect_with_audio_node(
-    buffer_source: &AudioBufferSourceNode,
-    destination: &AudioDestinationNode,
-) -> Result<AudioNode> {
-    buffer_source
+pub fn connect_with_audio_node(source: &AudioNode, destination: &AudioNode) -> Result<AudioNode> {
+    Ok(source
         .connect_with_audio_node(destination)
-        .map_err(|err| anyhow!("Could not connect audio source to destination {:#?}", err))
+        .map_err(|err| EngineError::browser("connect audio source to destination", err))?)
+}
+
+pub fn create_gain_node(ctx: &AudioContext) -> Result<GainNode> {
+    Ok(ctx
+        .create_gain()
+        .map_err(|err| EngineError::browser("create gain node", err))?)
+}
+
+pub fn set_gain(ctx: &AudioContext, gain_node: &GainNode, value: f32) -> Result<()> {
+    gain_node
+        .gain()
+        .set_value_at_time(value, ctx.current_time())
+        .map_err(|err| EngineError::browser("set gain", err))?;
+    Ok(())
 }
 
 pub fn create_track_source(
     ctx: &AudioContext,
     buffer: &AudioBuffer,
+    destination: &AudioNode,
 ) -> Result<AudioBufferSourceNode> {
     let track_source = create_buffer_source(ctx)?;
     track_source.set_buffer(Some(buffer));
-    connect_with_audio_node(&track_source, &ctx.destination())?;
+    connect_with_audio_node(&track_source, destination)?;
     Ok(track_source)
 }
 
@@ -37,26 +54,69 @@ pub enum Looping {
     Yes,
 }
 
-pub fn play_sound(ctx: &AudioContext, buffer: &AudioBuffer, looping: Looping) -> Result<()> {
-    let track_source = create_track_source(ctx, buffer)?;
+pub fn play_sound(
+    ctx: &AudioContext,
+    buffer: &AudioBuffer,
+    destination: &AudioNode,
+    looping: Looping,
+    offset: f64,
+) -> Result<AudioBufferSourceNode> {
+    let track_source = create_track_source(ctx, buffer, destination)?;
     if matches!(looping, Looping::Yes) {
         track_source.set_loop(true);
     }
     track_source
-        .start()
-        .map_err(|err| anyhow!("Could not start sound {:#?}", err))
+        .start_with_when_and_grain_offset(0.0, offset)
+        .map_err(|err| EngineError::browser("start sound", err))?;
+    Ok(track_source)
+}
+
+pub fn stop_sound(source: &AudioBufferSourceNode, when: f64) -> Result<()> {
+    Ok(AudioScheduledSourceNode::stop_with_when(source, when)
+        .map_err(|err| EngineError::browser("stop sound", err))?)
+}
+
+pub fn on_ended(source: &AudioBufferSourceNode, handler: &JsValue) {
+    AudioScheduledSourceNode::set_onended(source, Some(handler.unchecked_ref()));
+}
+
+pub fn ramp_gain(
+    ctx: &AudioContext,
+    gain_node: &GainNode,
+    from: f32,
+    to: f32,
+    duration: f64,
+) -> Result<()> {
+    let now = ctx.current_time();
+    gain_node
+        .gain()
+        .cancel_scheduled_values(now)
+        .and_then(|gain| gain.set_value_at_time(from, now))
+        .and_then(|gain| gain.linear_ramp_to_value_at_time(to, now + duration))
+        .map_err(|err| EngineError::browser("ramp gain", err))?;
+    Ok(())
+}
+
+pub async fn resume(ctx: &AudioContext) -> Result<()> {
+    JsFuture::from(
+        ctx.resume()
+            .map_err(|err| EngineError::browser("resume audio context", err))?,
+    )
+    .await
+    .map_err(|err| EngineError::browser("resume audio context", err))?;
+    Ok(())
 }
 
 pub async fn decode_audio_data(
     ctx: &AudioContext,
     array_buffer: &ArrayBuffer,
 ) -> Result<AudioBuffer> {
-    JsFuture::from(
+    Ok(JsFuture::from(
         ctx.decode_audio_data(array_buffer)
-            .map_err(|err| anyhow!("Could not decode audio from array buffer {:#?}", err))?,
+            .map_err(|err| EngineError::browser("decode audio from array buffer", err))?,
     )
     .await
-    .map_err(|err| anyhow!("Could not convert promise to future {:#?}", err))?
+    .map_err(|err| EngineError::browser("convert promise to future", err))?
     .dyn_into()
-    .map_err(|err| anyhow!("Could not convert to AudioBuffer {:#?}", err))
+    .map_err(|err| EngineError::conversion(err, "AudioBuffer"))?)
 }